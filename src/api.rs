@@ -0,0 +1,51 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{connection::SingleConnection, error::RconError};
+
+/// A future returned by the methods of [`RconConnection`](trait.RconConnection.html).
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The API shared by [`Connection`](struct.Connection.html) and [`ReConnection`](struct.ReConnection.html), so code
+/// can be generic over either of them, and be tested against a mock implementing it.
+///
+/// The methods return boxed futures, which keeps the trait usable as `dyn RconConnection`. Closing requires the
+/// connection to be sized, so boxed connections are dropped instead.
+///
+/// # Example
+/// ```rust
+/// use rercon::{Error, RconConnection};
+///
+/// async fn greet(connection: &mut impl RconConnection) -> Result<String, Error> {
+///     connection.exec("say Hello").await
+/// }
+/// ```
+pub trait RconConnection: Send {
+	/// Sends a command to the RCON server, like [`Connection::exec`](struct.Connection.html#method.exec).
+	fn exec<'a>(&'a mut self, cmd: &'a str) -> BoxFuture<'a, Result<String, RconError>>;
+
+	/// Closes the connection, like [`Connection::close`](struct.Connection.html#method.close).
+	fn close(self) -> BoxFuture<'static, ()>
+	where
+		Self: Sized;
+}
+
+impl RconConnection for SingleConnection {
+	fn exec<'a>(&'a mut self, cmd: &'a str) -> BoxFuture<'a, Result<String, RconError>> {
+		Box::pin(SingleConnection::exec_str(self, cmd))
+	}
+
+	fn close(self) -> BoxFuture<'static, ()> {
+		Box::pin(SingleConnection::close(self))
+	}
+}
+
+#[cfg(feature = "reconnection")]
+impl RconConnection for crate::reconnect::ReconnectingConnection {
+	fn exec<'a>(&'a mut self, cmd: &'a str) -> BoxFuture<'a, Result<String, RconError>> {
+		Box::pin(crate::reconnect::ReconnectingConnection::exec_str(self, cmd))
+	}
+
+	fn close(self) -> BoxFuture<'static, ()> {
+		Box::pin(crate::reconnect::ReconnectingConnection::close(self))
+	}
+}
@@ -0,0 +1,48 @@
+//! Helpers for Ark: Survival Evolved and Ark: Survival Ascended servers.
+
+use crate::{id::PlayerRef, parse::numbered_lines};
+
+/// A single player entry from the output of `ListPlayers`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Player {
+	/// The position of the player in the list.
+	pub index: usize,
+	/// The player's display name.
+	pub name: String,
+	/// The player's platform ID: a SteamID64 on Survival Evolved, or an Epic Online Services ID on Survival Ascended.
+	pub id: String,
+}
+
+impl Player {
+	/// The platform ID, as a [`PlayerRef::Steam`](../enum.PlayerRef.html#variant.Steam) on Survival Evolved or a
+	/// [`PlayerRef::Eos`](../enum.PlayerRef.html#variant.Eos) on Survival Ascended.
+	pub fn player_ref(&self) -> PlayerRef {
+		match self.id.parse() {
+			Ok(player_ref) => player_ref,
+			Err(never) => match never {},
+		}
+	}
+}
+
+/// Parses the output of `ListPlayers`, as returned by both Survival Evolved (`0. Name, 76561198000000000`) and
+/// Survival Ascended (`0. Name, 0002b3a5...`). Lines that do not match the format are skipped.
+pub fn parse_list_players(response: &str) -> Vec<Player> {
+	numbered_lines(response)
+		.filter_map(|(index, rest)| parse_player(index, rest))
+		.collect()
+}
+
+fn parse_player(index: usize, rest: &str) -> Option<Player> {
+	// Names may contain commas themselves, so the ID is whatever follows the last one.
+	let (name, id) = rest.rsplit_once(',')?;
+	let id = id.trim();
+	if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+		return None;
+	}
+
+	Some(Player {
+		index,
+		name: name.to_string(),
+		id: id.to_string(),
+	})
+}
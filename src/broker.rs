@@ -0,0 +1,87 @@
+use std::{io::ErrorKind, pin::Pin};
+
+use tokio::{
+	io::{AsyncRead, AsyncWrite},
+	net::TcpListener,
+};
+
+use crate::{
+	error::RconError::{self, IO},
+	handle::ConnectionHandle,
+	packet::{Packet, MAX_BODY_SIZE, MAX_PACKET_SIZE, TYPE_AUTH, TYPE_AUTH_RESPONSE, TYPE_RESPONSE},
+};
+
+impl ConnectionHandle {
+	/// Shares the connection with other processes, so several tools can use it without exceeding the connection
+	/// limit of the server. Accepts RCON connections on `listener` (which should be bound to a loopback address),
+	/// serving each like [`serve_client`](#method.serve_client). Keeps accepting until the listener fails.
+	pub async fn serve(&self, listener: TcpListener, password: impl ToString) -> Result<(), RconError> {
+		let password = password.to_string();
+		loop {
+			let (stream, _) = listener.accept().await?;
+			let handle = self.clone();
+			let password = password.clone();
+			tokio::spawn(async move {
+				// A misbehaving client only affects its own connection.
+				let _ = handle.serve_client(stream, &password).await;
+			});
+		}
+	}
+
+	/// Serves a single RCON client connected through `stream`, such as an accepted `TcpStream` or `UnixStream`,
+	/// until it disconnects. Any RCON client, including [`Connection`](struct.Connection.html), can connect this way,
+	/// authenticating with `password` instead of the password of the server. Its commands are run on this connection,
+	/// one by one with those of all other handles. If a command fails, the client is disconnected, so it sees the
+	/// failure as an [`IO error`](enum.Error.html#variant.IO). A client sending a malformed packet is disconnected
+	/// with an [`IO error`](enum.Error.html#variant.IO) of kind `InvalidData`, without affecting the connection.
+	pub async fn serve_client(
+		&self, mut stream: impl AsyncRead + AsyncWrite + Unpin, password: &str,
+	) -> Result<(), RconError> {
+		let mut authenticated = false;
+		loop {
+			// Clients only send commands, so anything longer is a malformed packet rather than a reason to allocate
+			let packet = match Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE).await {
+				Ok(packet) => packet,
+				Err(IO(e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+				Err(e) => return Err(e),
+			};
+			let id = packet.get_id();
+
+			if packet.get_packet_type() == TYPE_AUTH {
+				authenticated = packet.get_body() == password.as_bytes();
+				let id = if authenticated { id } else { -1 };
+				Packet::new(id, TYPE_AUTH_RESPONSE, "")
+					.send_internal(Pin::new(&mut stream))
+					.await?;
+				continue;
+			}
+			if !authenticated {
+				return Ok(());
+			}
+
+			// An empty command is most likely the terminator of the client, which we mirror without bothering the
+			// server. Any other terminator is simply run, its response ends the response of the client all the same.
+			let response = match packet.get_body() {
+				[] => String::new(),
+				cmd => self.exec_raw_bytes(cmd).await?,
+			};
+
+			// Responses are split up in packets the client accepts, always sending at least one.
+			let mut rest = response.as_str();
+			loop {
+				let mut end = rest.len().min(MAX_BODY_SIZE);
+				while !rest.is_char_boundary(end) {
+					end -= 1;
+				}
+				let (body, tail) = rest.split_at(end);
+				Packet::new(id, TYPE_RESPONSE, body)
+					.send_internal(Pin::new(&mut stream))
+					.await?;
+				rest = tail;
+				if rest.is_empty() {
+					break;
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use crate::{
+	connection::{
+		AddressFilter, CircuitBreaker, NewlineMode, Profile, ReconnectPolicy, Settings, ThrottleDetector,
+		UnsolicitedBuffer, MAX_AUTH_FAILURE_BACKOFF,
+	},
+	error::RconError::{self, InvalidSettings},
+	filter::ResponseFilter,
+	hook::ExecHook,
+	locale::Localization,
+	transcript::Transcript,
+};
+
+/// Builds [`Settings`](struct.Settings.html) one field at a time, checking the combination with
+/// [`Settings::validate`](struct.Settings.html#method.validate) once done. Unlike a struct literal, code using the
+/// builder keeps compiling as fields are added to `Settings`.
+///
+/// Every setter is named after the field it sets, and setters of optional fields take the value itself.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rercon::{Settings, SettingsBuilder};
+///
+/// let settings = SettingsBuilder::from(Settings::ark())
+///     .response_timeout(Duration::from_secs(5))
+///     .warm_standby(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(settings.response_timeout, Some(Duration::from_secs(5)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SettingsBuilder {
+	settings: Settings,
+}
+
+macro_rules! setters {
+	($($field:ident: $ty:ty),* $(,)?) => {$(
+		#[doc = concat!("Sets [`", stringify!($field), "`](struct.Settings.html#structfield.", stringify!($field), ").")]
+		pub fn $field(mut self, $field: $ty) -> Self {
+			self.settings.$field = $field;
+			self
+		}
+	)*};
+}
+
+macro_rules! optional_setters {
+	($($field:ident: $ty:ty),* $(,)?) => {$(
+		#[doc = concat!("Sets [`", stringify!($field), "`](struct.Settings.html#structfield.", stringify!($field), ").")]
+		pub fn $field(mut self, $field: $ty) -> Self {
+			self.settings.$field = Some($field);
+			self
+		}
+	)*};
+}
+
+impl SettingsBuilder {
+	setters! {
+		connect_timeout: Duration,
+		auth_timeout: Duration,
+		newline_mode: NewlineMode,
+		response_filters: Vec<ResponseFilter>,
+		lossy_utf8: bool,
+		skip_auth_without_password: bool,
+		source_quirks: bool,
+		auth_failure_backoff: Duration,
+		warm_standby: bool,
+		single_session: bool,
+		dedup_responses: bool,
+		throttle_retries: u32,
+		dry_run: bool,
+		latency_compensation: bool,
+		profile: Profile,
+		localization: Localization,
+	}
+
+	optional_setters! {
+		connect_budget: Duration,
+		address_filter: AddressFilter,
+		response_timeout: Duration,
+		auth_delay: Duration,
+		packet_pacing: Duration,
+		circuit_breaker: CircuitBreaker,
+		reconnect_jitter: Duration,
+		reconnect_policy: ReconnectPolicy,
+		exec_hook: ExecHook,
+		memory_limit: usize,
+		throttle_detector: ThrottleDetector,
+		transcript: Transcript,
+		unsolicited: UnsolicitedBuffer,
+	}
+
+	/// Sets [`terminator`](struct.Settings.html#structfield.terminator).
+	pub fn terminator(mut self, terminator: impl ToString) -> Self {
+		self.settings.terminator = terminator.to_string();
+		self
+	}
+
+	/// Sets [`restart_probe`](struct.Settings.html#structfield.restart_probe).
+	pub fn restart_probe(mut self, restart_probe: impl ToString) -> Self {
+		self.settings.restart_probe = Some(restart_probe.to_string());
+		self
+	}
+
+	/// Sets [`label`](struct.Settings.html#structfield.label).
+	pub fn label(mut self, label: impl ToString) -> Self {
+		self.settings.label = Some(label.to_string());
+		self
+	}
+
+	/// Sets [`verification`](struct.Settings.html#structfield.verification), like
+	/// [`Settings::verify_command`](struct.Settings.html#method.verify_command).
+	pub fn verify_command(mut self, cmd: impl ToString, expected: impl ToString) -> Self {
+		self.settings = self.settings.verify_command(cmd, expected);
+		self
+	}
+
+	/// Returns the settings, or [`InvalidSettings`](enum.Error.html#variant.InvalidSettings) if they fail
+	/// [`Settings::validate`](struct.Settings.html#method.validate).
+	pub fn build(self) -> Result<Settings, RconError> {
+		self.settings.validate()?;
+		Ok(self.settings)
+	}
+}
+
+impl From<Settings> for SettingsBuilder {
+	/// Starts from existing settings, such as one of the presets.
+	fn from(settings: Settings) -> Self {
+		SettingsBuilder { settings }
+	}
+}
+
+impl Settings {
+	/// Starts building settings from the defaults, see [`SettingsBuilder`](struct.SettingsBuilder.html).
+	pub fn builder() -> SettingsBuilder {
+		SettingsBuilder::default()
+	}
+
+	/// Checks for values that can't work, such as zero timeouts, and for options that contradict each other, failing
+	/// with [`InvalidSettings`](enum.Error.html#variant.InvalidSettings) describing the first problem found. Done by
+	/// [`SettingsBuilder::build`](struct.SettingsBuilder.html#method.build), but not when opening a connection.
+	pub fn validate(&self) -> Result<(), RconError> {
+		let zero = Duration::from_secs(0);
+		let problem = if self.connect_timeout == zero {
+			"connect_timeout is zero"
+		} else if self.connect_budget == Some(zero) {
+			"connect_budget is zero"
+		} else if self.auth_timeout == zero {
+			"auth_timeout is zero"
+		} else if self.response_timeout == Some(zero) {
+			"response_timeout is zero"
+		} else if self.auth_failure_backoff > MAX_AUTH_FAILURE_BACKOFF {
+			"auth_failure_backoff is longer than a day"
+		} else if self.memory_limit == Some(0) {
+			"memory_limit is zero"
+		} else if matches!(&self.circuit_breaker, Some(breaker) if breaker.failure_threshold == 0) {
+			"failure_threshold of the circuit_breaker is zero"
+		} else if matches!(&self.unsolicited, Some(buffer) if buffer.capacity == 0) {
+			"capacity of the unsolicited buffer is zero"
+		} else if self.warm_standby && self.single_session {
+			"warm_standby supersedes the active connection with single_session"
+		} else if self.throttle_retries > 0 && self.throttle_detector.is_none() {
+			"throttle_retries are set without a throttle_detector"
+		} else {
+			return Ok(());
+		};
+		Err(InvalidSettings(problem.to_string()))
+	}
+}
@@ -0,0 +1,68 @@
+use crate::{
+	connection::{NewlineMode, Settings},
+	packet::{MAX_BODY_SIZE, MAX_PACKET_SIZE},
+};
+
+/// Describes what a connection supports and which protocol strategies it uses, so tooling built on top of this
+/// library can adapt its behaviour without having to inspect the settings it was opened with.
+///
+/// Obtained through [`capabilities`](fn.capabilities.html) for the library defaults, or through
+/// [`Connection::capabilities`](struct.Connection.html#method.capabilities) for an open connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+	/// Largest packet that is sent, including its length prefix.
+	pub max_packet_size: usize,
+	/// Longest command that can be sent in a single packet.
+	pub max_command_size: usize,
+	/// Command sent after every exec to detect the end of a response, empty for the regular empty-command strategy.
+	pub terminator: String,
+	/// How newlines embedded in commands are handled.
+	pub newline_mode: NewlineMode,
+	/// Whether Source engine protocol quirks are handled.
+	pub source_quirks: bool,
+	/// Whether duplicated response packets are dropped.
+	pub dedup_responses: bool,
+	/// Whether invalid UTF-8 in responses is replaced rather than rejected.
+	pub lossy_utf8: bool,
+	/// Whether multiple commands can be in flight at once on a single connection.
+	pub multiplexing: bool,
+	/// The optional cargo features this library was compiled with.
+	pub features: Vec<&'static str>,
+}
+
+impl Capabilities {
+	pub(crate) fn new(settings: &Settings) -> Self {
+		let features = [
+			("reconnection", cfg!(feature = "reconnection")),
+			("ark", cfg!(feature = "ark")),
+			("minecraft", cfg!(feature = "minecraft")),
+			("source", cfg!(feature = "source")),
+			("serde", cfg!(feature = "serde")),
+			("json", cfg!(feature = "json")),
+			("unicode", cfg!(feature = "unicode")),
+			("stream", cfg!(feature = "stream")),
+			("broker", cfg!(feature = "broker")),
+		];
+
+		Self {
+			max_packet_size: MAX_PACKET_SIZE,
+			max_command_size: MAX_BODY_SIZE,
+			terminator: settings.terminator.clone(),
+			newline_mode: settings.newline_mode,
+			source_quirks: settings.source_quirks,
+			dedup_responses: settings.dedup_responses,
+			lossy_utf8: settings.lossy_utf8,
+			multiplexing: true,
+			features: features
+				.iter()
+				.filter(|(_, enabled)| *enabled)
+				.map(|(name, _)| *name)
+				.collect(),
+		}
+	}
+}
+
+/// Returns the capabilities of a connection opened with [`Settings::default`](struct.Settings.html).
+pub fn capabilities() -> Capabilities {
+	Capabilities::new(&Settings::default())
+}
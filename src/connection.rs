@@ -1,53 +1,472 @@
 use std::{
-	fmt::{self, Display, Formatter},
+	collections::{HashMap, VecDeque},
+	fmt::{Debug, Formatter},
+	future::{pending, poll_fn, Future},
 	io::ErrorKind,
+	mem,
 	net::SocketAddr::{self, V4, V6},
 	pin::Pin,
 	sync::{
-		atomic::{AtomicI32, Ordering},
+		atomic::{AtomicI32, AtomicU64, Ordering},
 		Arc,
 	},
+	task::Poll,
 	time::Duration,
 };
 
 use tokio::{
-	io::AsyncRead,
+	io::{AsyncWrite, AsyncWriteExt},
 	net::{
 		lookup_host,
 		tcp::{OwnedReadHalf, OwnedWriteHalf},
 		TcpStream, ToSocketAddrs,
 	},
 	select,
-	sync::{mpsc, Notify},
+	sync::{mpsc, Mutex, Notify},
 	task::JoinHandle,
-	time::{sleep, timeout},
+	time::{sleep, sleep_until, timeout, timeout_at, Instant},
 };
 
+#[cfg(feature = "stream")]
+use crate::stream::ResponseStream;
 use crate::{
-	error::RconError::{self, PasswordIncorrect, UnexpectedPacket, IO},
-	packet::{Packet, TYPE_AUTH, TYPE_AUTH_RESPONSE, TYPE_EXEC, TYPE_RESPONSE},
+	capabilities::Capabilities,
+	error::RconError::{
+		self, Cancelled, CommandContainsNewline, ConnectionClosing, InternalError, MemoryLimitExceeded, PartialWrite,
+		PasswordIncorrect, Superseded, Throttled, Timeout, UnexpectedPacket, UnsolicitedOverflow, VerificationFailed,
+		IO,
+	},
+	filter::ResponseFilter,
+	hook::ExecHook,
+	locale::Localization,
+	packet::{Packet, MAX_READ_PACKET_SIZE, PACKET_OVERHEAD, TYPE_AUTH, TYPE_AUTH_RESPONSE, TYPE_EXEC, TYPE_RESPONSE},
+	supervise::catch_unwind,
+	transcript::Transcript,
 };
 
+/// Predicate deciding whether a resolved address may be connected to, see
+/// [`Settings::address_filter`](struct.Settings.html#structfield.address_filter).
+pub type AddressFilter = Arc<dyn Fn(&SocketAddr) -> bool + Send + Sync>;
+
+/// Inspects a response, returning how long the server asked to wait if it is a throttle message rather than the
+/// actual response, see [`throttle_detector`](struct.Settings.html#structfield.throttle_detector).
+pub type ThrottleDetector = Arc<dyn Fn(&str) -> Option<Duration> + Send + Sync>;
+
 /// Settings struct which can be used to adapt behaviour slightly which might help with nonconformant servers.
 #[derive(Clone)]
 pub struct Settings {
 	/// Maximum time allowed to set up a Tcp connection before giving up with a timeout. The maximum timeout possible
 	/// will be this multiplied by the amount of IPs the host resolves to.
 	pub connect_timeout: Duration,
+	/// Maximum total time allowed for resolving the host and trying all of its addresses, capping the combined
+	/// [`connect_timeout`](#structfield.connect_timeout)s of hosts that resolve to many addresses.
+	pub connect_budget: Option<Duration>,
+	/// Predicate every resolved address has to pass before it is connected to, for example to ensure it lies within a
+	/// trusted network so a hijacked DNS record can't redirect the password to another host.
+	pub address_filter: Option<AddressFilter>,
+	/// Maximum time to wait for the server to answer the auth packet, so servers that accept connections but never
+	/// respond can't hang [`open`](struct.Connection.html#method.open) forever.
+	pub auth_timeout: Duration,
+	/// Maximum time to wait for the full response to a command, after which the exec fails with
+	/// [`Timeout`](enum.Error.html#variant.Timeout). Applies to every exec that doesn't set its own limit through
+	/// [`exec_with_timeout`](struct.Connection.html#method.exec_with_timeout).
+	pub response_timeout: Option<Duration>,
 	/// Delay inbetween TCP connection establishment and sending of the first (auth) packet, needed for older Minecraft
 	/// servers.
 	pub auth_delay: Option<Duration>,
+	/// Minimum delay between two packets sent on a connection, for older servers that drop packets arriving
+	/// back-to-back in the same TCP segment.
+	pub packet_pacing: Option<Duration>,
+	/// How newlines embedded in a command are handled, as several games treat each line as a separate console command.
+	pub newline_mode: NewlineMode,
+	/// Filters applied in order to every response before it is returned from `exec`.
+	pub response_filters: Vec<ResponseFilter>,
+	/// Body of the command sent after every exec to detect the end of a response. Defaults to an empty command, but
+	/// some servers ignore those and need a harmless real command instead.
+	pub terminator: String,
+	/// Replace invalid UTF-8 in responses with `U+FFFD` instead of failing with
+	/// [`UTFEncoding`](enum.Error.html#variant.UTFEncoding), for servers that mangle Unicode player names.
+	pub lossy_utf8: bool,
+	/// Skips the auth exchange when the password is empty, for servers that don't expect one at all when no password
+	/// is configured. By default, an empty password is sent like any other.
+	pub skip_auth_without_password: bool,
+	/// Enables handling of Source engine (CS:GO, CS2) protocol quirks, such as consuming the empty marker packets
+	/// some servers send after fragmented responses.
+	pub source_quirks: bool,
+	/// Command run by [`ReConnection`](struct.ReConnection.html) after every (re)connect, whose response should
+	/// identify the running server instance (such as its start time). A different response after reconnecting emits
+	/// [`Event::ServerRestarted`](enum.Event.html#variant.ServerRestarted).
+	pub restart_probe: Option<String>,
+	/// Circuit breaker used by [`ReConnection`](struct.ReConnection.html) to back off from servers that keep
+	/// accepting connections only to drop them again.
+	pub circuit_breaker: Option<CircuitBreaker>,
+	/// Delay used by [`ReConnection`](struct.ReConnection.html) before retrying after a failed authentication, which
+	/// doubles with every consecutive failure (up to 32 times this value) to avoid being banned by the server. The
+	/// delay never exceeds a day.
+	pub auth_failure_backoff: Duration,
+	/// Maximum random delay [`ReConnection`](struct.ReConnection.html) adds before every reconnect attempt, sampled anew for every attempt. When many
+	/// connections to the same host drop at once, this spreads out their reconnects rather than having all of them
+	/// hit the host at the same moment once it comes back.
+	pub reconnect_jitter: Option<Duration>,
+	/// Keeps a second, authenticated connection ready in [`ReConnection`](struct.ReConnection.html), which takes
+	/// over immediately when the active one fails. Only the exec that ran into the failure returns an error, instead
+	/// of every exec until reconnecting has finished.
+	pub warm_standby: bool,
+	/// Set for servers that allow only a single RCON client at a time, and silently close the connection of the
+	/// previous client once another one connects. The server closing the connection while no command is in flight is
+	/// then taken as being superseded by another client, failing with
+	/// [`Superseded`](enum.Error.html#variant.Superseded), for which the
+	/// [`reconnect_policy`](#structfield.reconnect_policy) decides whether [`ReConnection`](struct.ReConnection.html)
+	/// takes the session back. Should not be combined with [`warm_standby`](#structfield.warm_standby), whose spare
+	/// connection would supersede the active one.
+	pub single_session: bool,
+	/// Decides after every failed reconnect attempt of [`ReConnection`](struct.ReConnection.html) whether to keep
+	/// trying, for example to give up once the password is rejected. Also decides whether to reconnect at all after
+	/// being [`Superseded`](enum.Error.html#variant.Superseded) by another client. If unset, reconnecting never gives
+	/// up.
+	pub reconnect_policy: Option<ReconnectPolicy>,
+	/// Drops a response packet if it is identical (same id and body) to the one received right before it, working
+	/// around buggy plugins that send every response twice.
+	pub dedup_responses: bool,
+	/// A command and a substring its response must contain, which is run right after authenticating to catch
+	/// connecting to the wrong server or port. Can be set through [`verify_command`](#method.verify_command).
+	pub verification: Option<(String, String)>,
+	/// Called after every exec with its duration and outcome, see [`ExecHook`](struct.ExecHook.html).
+	pub exec_hook: Option<ExecHook>,
+	/// Maximum amount of memory, in bytes, a connection may use to hold a response and the
+	/// [`unsolicited`](#structfield.unsolicited) messages, so a malfunctioning server can't exhaust the memory of the
+	/// process. Responses that don't fit fail with [`MemoryLimitExceeded`](enum.Error.html#variant.MemoryLimitExceeded),
+	/// and unsolicited messages that don't fit are handled like a full buffer. A single packet larger than the limit
+	/// is not read at all, failing the connection with an [`IO error`](enum.Error.html#variant.IO) instead.
+	pub memory_limit: Option<usize>,
+	/// Recognizes the throttle messages of servers that rate limit commands (such as "Too many commands, wait 5s"),
+	/// see [`ThrottleDetector`](type.ThrottleDetector.html). None of the games with a preset do this, so none of the
+	/// presets set one.
+	pub throttle_detector: Option<ThrottleDetector>,
+	/// How many times a throttled command is sent again after waiting as long as the server asked, before failing
+	/// with [`Throttled`](enum.Error.html#variant.Throttled).
+	pub throttle_retries: u32,
+	/// Rehearses scripts without touching the server: commands are validated, filtered, recorded in the
+	/// [`transcript`](#structfield.transcript) and passed to the [`exec_hook`](#structfield.exec_hook) as usual, but
+	/// never sent, and respond with an empty string. The connection is still opened and authenticated, but the
+	/// [`verification`](#structfield.verification) is skipped.
+	pub dry_run: bool,
+	/// Sends the commands of [`exec_at`](struct.Connection.html#method.exec_at) half the measured
+	/// [`round_trip_time`](struct.Connection.html#method.round_trip_time) early, so they reach the server at the
+	/// requested time rather than late, for example to broadcast a message exactly on the hour.
+	pub latency_compensation: bool,
+	/// The game the server runs, set by the presets such as [`Settings::ark`](#method.ark).
+	pub profile: Profile,
+	/// Alternative wordings of what helpers such as [`server_info`](struct.Connection.html#method.server_info) look
+	/// for in responses, for servers that localize them, see [`Localization`](struct.Localization.html).
+	pub localization: Localization,
+	/// Name of the connection, included in the spans emitted with the `tracing` feature to tell connections apart.
+	pub label: Option<String>,
+	/// Records every command and its response, see [`Transcript`](struct.Transcript.html).
+	pub transcript: Option<Transcript>,
+	/// Buffers response packets the server sends outside of a request (such as chat or log messages on some
+	/// servers), which can be retrieved with [`drain_unsolicited`](struct.Connection.html#method.drain_unsolicited).
+	/// If unset, these packets are discarded.
+	pub unsolicited: Option<UnsolicitedBuffer>,
+}
+
+impl Debug for Settings {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Settings")
+			.field("connect_timeout", &self.connect_timeout)
+			.field("connect_budget", &self.connect_budget)
+			.field("address_filter", &self.address_filter.as_ref().map(|_| Opaque))
+			.field("auth_timeout", &self.auth_timeout)
+			.field("response_timeout", &self.response_timeout)
+			.field("auth_delay", &self.auth_delay)
+			.field("packet_pacing", &self.packet_pacing)
+			.field("newline_mode", &self.newline_mode)
+			.field(
+				"response_filters",
+				&self.response_filters.iter().map(|_| Opaque).collect::<Vec<_>>(),
+			)
+			.field("terminator", &self.terminator)
+			.field("lossy_utf8", &self.lossy_utf8)
+			.field("skip_auth_without_password", &self.skip_auth_without_password)
+			.field("source_quirks", &self.source_quirks)
+			.field("restart_probe", &self.restart_probe)
+			.field("circuit_breaker", &self.circuit_breaker)
+			.field("auth_failure_backoff", &self.auth_failure_backoff)
+			.field("reconnect_jitter", &self.reconnect_jitter)
+			.field("warm_standby", &self.warm_standby)
+			.field("single_session", &self.single_session)
+			.field("reconnect_policy", &self.reconnect_policy.as_ref().map(|_| Opaque))
+			.field("dedup_responses", &self.dedup_responses)
+			.field("verification", &self.verification)
+			.field("exec_hook", &self.exec_hook.as_ref().map(|_| Opaque))
+			.field("memory_limit", &self.memory_limit)
+			.field("throttle_detector", &self.throttle_detector.as_ref().map(|_| Opaque))
+			.field("throttle_retries", &self.throttle_retries)
+			.field("dry_run", &self.dry_run)
+			.field("latency_compensation", &self.latency_compensation)
+			.field("profile", &self.profile)
+			.field("localization", &self.localization)
+			.field("label", &self.label)
+			.field("transcript", &self.transcript.as_ref().map(|_| Opaque))
+			.field("unsolicited", &self.unsolicited)
+			.finish()
+	}
+}
+
+/// Stands in for callbacks and other values without a `Debug` representation of their own.
+struct Opaque;
+
+impl Debug for Opaque {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str("..")
+	}
 }
 
 impl Default for Settings {
 	fn default() -> Self {
 		Settings {
 			connect_timeout: Duration::from_secs(10),
+			connect_budget: None,
+			address_filter: None,
+			auth_timeout: Duration::from_secs(10),
+			response_timeout: None,
 			auth_delay: None,
+			packet_pacing: None,
+			newline_mode: NewlineMode::Send,
+			response_filters: Vec::new(),
+			terminator: String::new(),
+			lossy_utf8: false,
+			skip_auth_without_password: false,
+			source_quirks: false,
+			restart_probe: None,
+			circuit_breaker: None,
+			auth_failure_backoff: Duration::from_secs(30),
+			reconnect_jitter: None,
+			warm_standby: false,
+			single_session: false,
+			reconnect_policy: None,
+			dedup_responses: false,
+			verification: None,
+			exec_hook: None,
+			memory_limit: None,
+			throttle_detector: None,
+			throttle_retries: 0,
+			dry_run: false,
+			latency_compensation: false,
+			profile: Profile::Generic,
+			localization: Localization::default(),
+			label: None,
+			transcript: None,
+			unsolicited: None,
+		}
+	}
+}
+
+/// Configuration of the circuit breaker of [`ReConnection`](struct.ReConnection.html).
+///
+/// Once `failure_threshold` consecutive disconnects or failed reconnect attempts occur without a successful exec in
+/// between, the breaker opens: no reconnects are attempted for `cooldown`, and `exec` fails fast with
+/// [`CircuitOpen`](enum.Error.html#variant.CircuitOpen). Afterwards a single attempt is allowed, and the breaker
+/// closes again after the next successful exec.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+	/// Amount of consecutive failures after which the breaker opens.
+	pub failure_threshold: u32,
+	/// How long the breaker stays open.
+	pub cooldown: Duration,
+}
+
+/// Callback deciding whether [`ReConnection`](struct.ReConnection.html) keeps trying after a failed reconnect
+/// attempt, see [`Settings::reconnect_policy`](struct.Settings.html#structfield.reconnect_policy).
+pub type ReconnectPolicy = Arc<dyn Fn(FailureKind, &RconError) -> ReconnectDecision + Send + Sync>;
+
+/// Classification of the error a connection attempt failed with, passed to the
+/// [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+	/// The host could not be resolved.
+	Dns,
+	/// The server refused the connection, usually because it is not running.
+	Refused,
+	/// Connecting timed out.
+	Timeout,
+	/// The server rejected the password.
+	Auth,
+	/// The server did not behave as an RCON server is expected to, such as when connecting to the wrong port.
+	Protocol,
+	/// The server closed the connection in favour of another client, see
+	/// [`single_session`](struct.Settings.html#structfield.single_session).
+	Superseded,
+	/// Any other failure, such as the connection being dropped.
+	Other,
+}
+
+impl FailureKind {
+	/// Classifies the error a connection attempt failed with.
+	pub fn of(error: &RconError) -> Self {
+		match error {
+			IO(e) => match e.kind() {
+				ErrorKind::AddrNotAvailable => Self::Dns,
+				ErrorKind::ConnectionRefused => Self::Refused,
+				ErrorKind::TimedOut => Self::Timeout,
+				_ => Self::Other,
+			},
+			PasswordIncorrect(_) => Self::Auth,
+			Timeout(_) => Self::Timeout,
+			Superseded => Self::Superseded,
+			UnexpectedPacket(_) | RconError::UTFEncoding(_) | VerificationFailed(_) => Self::Protocol,
+			_ => Self::Other,
 		}
 	}
 }
 
+/// Returned by a [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy) to decide how to proceed
+/// after a failed reconnect attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReconnectDecision {
+	/// Keep trying to reconnect.
+	Retry,
+	/// Stop reconnecting, after which `exec` fails with [`GaveUp`](enum.Error.html#variant.GaveUp).
+	GiveUp,
+}
+
+/// Configuration of the buffer for [`unsolicited`](struct.Settings.html#structfield.unsolicited) messages.
+#[derive(Clone, Debug)]
+pub struct UnsolicitedBuffer {
+	/// Maximum amount of messages kept until they are drained.
+	pub capacity: usize,
+	/// What happens to messages arriving while the buffer is full.
+	pub overflow: OverflowPolicy,
+}
+
+/// Counters of the packets a connection has received outside of a request, see
+/// [`Connection::stats`](struct.Connection.html#method.stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+	/// Empty packets, which some servers send periodically as keepalives. These are always discarded.
+	pub keepalive_frames: u64,
+	/// Non-empty packets, which are kept if an [`unsolicited`](struct.Settings.html#structfield.unsolicited) buffer
+	/// is configured.
+	pub unsolicited_packets: u64,
+	/// Non-empty packets that were discarded, because no [`unsolicited`](struct.Settings.html#structfield.unsolicited)
+	/// buffer is configured or because it overflowed. Each of these is also logged with the `tracing` feature, so a
+	/// server pushing data the application doesn't pick up doesn't go unnoticed.
+	pub discarded_packets: u64,
+}
+
+/// What happens to unsolicited messages arriving while their buffer is full, see
+/// [`UnsolicitedBuffer`](struct.UnsolicitedBuffer.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+	/// Discard the oldest buffered message to make room.
+	DropOldest,
+	/// Discard the arriving message.
+	DropNewest,
+	/// Discard the arriving message, and fail the next
+	/// [`drain_unsolicited`](struct.Connection.html#method.drain_unsolicited) with
+	/// [`UnsolicitedOverflow`](enum.Error.html#variant.UnsolicitedOverflow).
+	Error,
+}
+
+/// Longest [`auth_failure_backoff`](struct.Settings.html#structfield.auth_failure_backoff) accepted.
+pub(crate) const MAX_AUTH_FAILURE_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Settings {
+	/// The largest packet the server may send. A single packet above the memory limit can never fit, so it is
+	/// rejected before allocating it rather than after.
+	fn max_packet_size(&self) -> usize {
+		match self.memory_limit {
+			Some(limit) => limit.saturating_add(PACKET_OVERHEAD).min(MAX_READ_PACKET_SIZE),
+			None => MAX_READ_PACKET_SIZE,
+		}
+	}
+
+	/// Verifies every new connection by running `cmd` right after authenticating, failing
+	/// [`open`](struct.Connection.html#method.open) with [`VerificationFailed`](enum.Error.html#variant.VerificationFailed)
+	/// if the response does not contain `expected`.
+	pub fn verify_command(mut self, cmd: impl ToString, expected: impl ToString) -> Self {
+		self.verification = Some((cmd.to_string(), expected.to_string()));
+		self
+	}
+
+	/// Default settings with the response filters suited for Ark: Survival Evolved.
+	pub fn ark() -> Self {
+		Settings {
+			response_filters: vec![ResponseFilter::strip_ark_no_response(), ResponseFilter::trim_end()],
+			profile: Profile::Ark,
+			..Settings::default()
+		}
+	}
+
+	/// Default settings suited for Ark: Survival Ascended. Like [`ark`](#method.ark), the placeholder for commands
+	/// without output is stripped, in the form Ark: Survival Ascended sends it. On top of that, invalid UTF-8 in
+	/// Unicode player names is replaced rather than failing the exec, and commands time out after 30 seconds, as
+	/// busy servers occasionally leave a command unanswered.
+	pub fn ark_ascended() -> Self {
+		Settings {
+			response_filters: vec![ResponseFilter::strip_ark_no_response(), ResponseFilter::trim_end()],
+			response_timeout: Some(Duration::from_secs(30)),
+			lossy_utf8: true,
+			profile: Profile::Ark,
+			..Settings::default()
+		}
+	}
+
+	/// Default settings with the quirks of Source engine games, such as CS:GO and CS2, enabled.
+	pub fn source() -> Self {
+		Settings {
+			source_quirks: true,
+			profile: Profile::Source,
+			..Settings::default()
+		}
+	}
+
+	/// Default settings with the response filters suited for Minecraft.
+	pub fn minecraft() -> Self {
+		Settings {
+			response_filters: vec![ResponseFilter::strip_minecraft_formatting(), ResponseFilter::trim_end()],
+			profile: Profile::Minecraft,
+			..Settings::default()
+		}
+	}
+}
+
+/// The game a server runs, set by the [`Settings`](struct.Settings.html) presets, which determines the commands used
+/// by game-agnostic helpers such as [`server_info`](struct.Connection.html#method.server_info).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+	/// Any Valve RCON compliant server.
+	Generic,
+	/// Ark: Survival Evolved or Ark: Survival Ascended.
+	Ark,
+	/// A Source engine game, such as CS:GO or CS2.
+	Source,
+	/// Minecraft.
+	Minecraft,
+}
+
+/// Describes how [`exec`](struct.Connection.html#method.exec) treats newlines embedded in a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NewlineMode {
+	/// Refuse to send the command, returning [`CommandContainsNewline`](enum.Error.html#variant.CommandContainsNewline).
+	Reject,
+	/// Send the command as-is, leaving it up to the server to interpret the newlines.
+	Send,
+	/// Send every non-empty line as a separate command, combining their responses.
+	Split,
+}
+
+/// Describes whether [`exec_all`](struct.Connection.html#method.exec_all) keeps going after a command fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchPolicy {
+	/// Run every command, regardless of the results of the commands before it.
+	Continue,
+	/// Stop at the first command that fails, leaving the commands after it unsent.
+	StopOnError,
+}
+
 /// Represents a single-established RCON connection to the server, which will not automatically reconnect once the connection has failed.
 /// This struct will instead opt to return [`IO errors`](enum.Error.html#variant.IO), leaving connection responsibility in the callers hands.
 ///
@@ -62,65 +481,651 @@ impl Default for Settings {
 ///     println!("Reply from server: {}", reply);
 /// }
 /// ```
+///
+/// Commands can be sent from multiple tasks at once (for example by sharing the connection through an `Arc`), in
+/// which case they are all in flight simultaneously and every reply is routed to its caller by packet ID. Servers that
+/// can't keep up with multiple commands at once are better served by
+/// [`into_handle`](struct.Connection.html#method.into_handle), which sends them one by one.
 pub struct SingleConnection {
-	write: OwnedWriteHalf,
-	counter: i32,
+	writer: Mutex<Writer>,
+	counter: AtomicI32,
+	// Smoothed round-trip time in nanoseconds, zero until the first response arrived
+	round_trip: AtomicU64,
 	receiver: ReceiverHandle,
+	settings: Settings,
+	peer_addr: SocketAddr,
+	local_addr: SocketAddr,
+	connected_since: Instant,
+}
+
+impl Debug for SingleConnection {
+	/// Shows where the connection goes and how it is doing, but never the password, which isn't kept around.
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let (in_flight, failure) = {
+			let requests = self.receiver.shared.requests();
+			let failure = requests.failure.as_ref().map(|failure| failure.to_error().to_string());
+			(requests.pending.len(), failure)
+		};
+		f.debug_struct("Connection")
+			.field("peer_addr", &self.peer_addr)
+			.field("local_addr", &self.local_addr)
+			.field("connected_for", &self.connected_since.elapsed())
+			.field("failure", &failure)
+			.field("in_flight", &in_flight)
+			.field("round_trip_time", &self.round_trip_time())
+			.field("stats", &self.stats())
+			.field("settings", &self.settings)
+			.finish()
+	}
+}
+
+/// Write half of the connection, which is locked for as long as a packet is being written.
+struct Writer {
+	write: OwnedWriteHalf,
+	// When the last packet was sent, for packet_pacing
+	last_sent: Option<Instant>,
+	// Set while a packet is being written, so a write that was cancelled halfway can be detected
+	writing: bool,
+	// The packet being written, kept around to reuse its allocation
+	buf: Vec<u8>,
 }
 
 impl SingleConnection {
 	/// Opens a new RCON connection, with an optional timeout, and authenticates the connection to the remote server.
 	/// If connect_timeout is set to None, a default timeout of 10 seconds will be used.
 	pub async fn open(address: impl ToSocketAddrs, pass: impl ToString, settings: Settings) -> Result<Self, RconError> {
-		let stream = try_connect(address, settings.connect_timeout).await?;
+		let stream = match settings.connect_budget {
+			Some(budget) => timeout(budget, try_connect(address, &settings))
+				.await
+				.map_err(|_| IO(std::io::Error::new(ErrorKind::TimedOut, "connect budget exceeded")))??,
+			None => try_connect(address, &settings).await?,
+		};
+		Self::open_with_stream(stream, pass, settings).await
+	}
+
+	/// Authenticates over a stream that is already connected to the server, like [`open`](#method.open) does after
+	/// connecting, for callers with connection logic of their own, such as a custom dialer or a proxy that hands out
+	/// a plain `TcpStream` once its handshake is done. The connection settings, such as
+	/// [`connect_timeout`](struct.Settings.html#structfield.connect_timeout), are not used.
+	///
+	/// [`ReConnection`](struct.ReConnection.html) has no equivalent, as it has to be able to connect again by itself.
+	pub async fn open_with_stream(
+		stream: TcpStream, pass: impl ToString, settings: Settings,
+	) -> Result<Self, RconError> {
+		let (peer_addr, local_addr) = (stream.peer_addr()?, stream.local_addr()?);
 		let (mut read, mut write) = stream.into_split();
 
 		if let Some(auth_delay) = settings.auth_delay {
 			sleep(auth_delay).await;
 		}
 
-		Packet::new(0, TYPE_AUTH, pass.to_string())
-			.send_internal(Pin::new(&mut write))
+		// An empty password is authenticated like any other, unless the server skips the exchange for those
+		let pass = pass.to_string();
+		if !(pass.is_empty() && settings.skip_auth_without_password) {
+			let max_len = settings.max_packet_size();
+			timeout(
+				settings.auth_timeout,
+				authenticate(&mut read, &mut write, pass, max_len),
+			)
+			.await
+			.map_err(|_| IO(std::io::Error::new(ErrorKind::TimedOut, "auth response timed out")))??;
+		}
+
+		let receiver = ReceiverHandle::new(read, &settings);
+		let verification = settings.verification.clone();
+
+		let connection = Self {
+			writer: Mutex::new(Writer {
+				write,
+				last_sent: None,
+				writing: false,
+				buf: Vec::new(),
+			}),
+			counter: AtomicI32::new(0),
+			round_trip: AtomicU64::new(0),
+			receiver,
+			settings,
+			peer_addr,
+			local_addr,
+			connected_since: Instant::now(),
+		};
+
+		// Make sure we're talking to the server we expect, before handing out the connection
+		if let (Some((cmd, expected)), false) = (verification, connection.settings.dry_run) {
+			let response = connection.exec(cmd).await?;
+			if !response.contains(&expected) {
+				connection.close().await;
+				return Err(VerificationFailed(response));
+			}
+		}
+
+		Ok(connection)
+	}
+
+	/// Sends a command to the RCON server, returning the combined reply (in case there are multiple packets) or an error.
+	pub async fn exec(&self, cmd: impl ToString) -> Result<String, RconError> {
+		self.exec_raw_bytes(cmd.to_string().as_bytes()).await
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), but borrows the command instead of converting
+	/// it with `to_string`, so sending a `&'static str` or a reused `String` doesn't allocate. The command is written
+	/// straight into the send buffer of the connection.
+	pub async fn exec_str(&self, cmd: &str) -> Result<String, RconError> {
+		self.exec_raw_bytes(cmd.as_bytes()).await
+	}
+
+	/// Sends a command consisting of raw bytes to the RCON server, for servers or mods that embed binary payloads in
+	/// their commands. The command is not required to be valid UTF-8, only the packet length limit is enforced.
+	pub async fn exec_raw_bytes(&self, cmd: &[u8]) -> Result<String, RconError> {
+		self.exec_impl(cmd, &mut |_| (), &mut Cutoff::default(), Interrupt::default())
+			.await
+	}
+
+	/// Sends a command to the RCON server, returning the individual response packets instead of their combined body,
+	/// for inspecting their IDs and types or how the server split up a response. The mirrored terminator is not
+	/// included. The packets are returned as received, so neither the
+	/// [`response_filters`](struct.Settings.html#structfield.response_filters) nor the
+	/// [`throttle_detector`](struct.Settings.html#structfield.throttle_detector) are applied, and commands containing
+	/// newlines are sent as a single packet unless the [`newline_mode`](struct.Settings.html#structfield.newline_mode)
+	/// rejects them.
+	pub async fn exec_raw(&self, cmd: impl ToString) -> Result<Vec<Packet>, RconError> {
+		let cmd = cmd.to_string();
+		let start = Instant::now();
+		let mut interrupt = Interrupt::default().or_timeout(self.settings.response_timeout);
+		let mut packets = Vec::new();
+		let on_packet = &mut |packet| packets.push(packet);
+		let result = self.exec_packets(cmd.as_bytes(), &mut interrupt, on_packet).await;
+
+		// The transcript and exec hook see the combined body, like for any other exec
+		let result = result.map(|()| {
+			let bodies = packets.iter().map(|packet| String::from_utf8_lossy(packet.get_body()));
+			bodies.collect::<String>()
+		});
+		self.record(cmd.as_bytes(), start, &result);
+		result.map(|_| packets)
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), returning the response along with its latency,
+	/// the amount of packets it was made up of and the packet IDs used, for monitoring and debugging. Like
+	/// [`exec_raw`](#method.exec_raw), the command is always sent as a single packet and the
+	/// [`throttle_detector`](struct.Settings.html#structfield.throttle_detector) is not applied.
+	pub async fn exec_detailed(&self, cmd: impl ToString) -> Result<ExecDetails, RconError> {
+		let cmd = cmd.to_string();
+		let start = Instant::now();
+		let (result, details) = match self.exec_exchange(cmd.as_bytes(), start).await {
+			Ok(mut details) => (Ok(mem::take(&mut details.response)), Some(details)),
+			Err(e) => (Err(e), None),
+		};
+		self.record(cmd.as_bytes(), start, &result);
+		result.map(|response| ExecDetails {
+			response,
+			..details.expect("set on success")
+		})
+	}
+
+	async fn exec_exchange(&self, cmd: &[u8], start: Instant) -> Result<ExecDetails, RconError> {
+		let mut interrupt = Interrupt::default().or_timeout(self.settings.response_timeout);
+		let mut exchange = Exchange::start(self, cmd).await?;
+		let mut response = Vec::new();
+		let mut packets = 0;
+		while let Some(packet) = exchange.next(&mut interrupt).await? {
+			response.extend_from_slice(packet.get_body());
+			packets += 1;
+		}
+
+		let response = match self.settings.lossy_utf8 {
+			true => String::from_utf8_lossy(&response).into_owned(),
+			false => String::from_utf8(response)?,
+		};
+		Ok(ExecDetails {
+			response: self.filter(response),
+			latency: start.elapsed(),
+			packets,
+			request_id: exchange.id,
+			end_id: exchange.end_id,
+		})
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), invoking `on_fragment` for every response
+	/// packet as it arrives, so long outputs can be shown while they stream in. Fragments are passed as received,
+	/// before any [`response_filters`](struct.Settings.html#structfield.response_filters) are applied.
+	pub async fn exec_with_progress(
+		&self, cmd: impl ToString, mut on_fragment: impl FnMut(&str),
+	) -> Result<String, RconError> {
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut on_fragment,
+			&mut Cutoff::default(),
+			Interrupt::default(),
+		)
+		.await
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), but stops collecting the response once it
+	/// reaches `max_bytes` instead of holding on to all of it, for commands with huge outputs of which only the start
+	/// is needed. The rest of the response is still read from the connection, but discarded. The cutoff is applied
+	/// before any [`response_filters`](struct.Settings.html#structfield.response_filters).
+	pub async fn exec_with_cutoff(&self, cmd: impl ToString, max_bytes: usize) -> Result<CutoffResponse, RconError> {
+		let mut cutoff = Cutoff::new(max_bytes);
+		let response = self
+			.exec_impl(
+				cmd.to_string().as_bytes(),
+				&mut |_| (),
+				&mut cutoff,
+				Interrupt::default(),
+			)
 			.await?;
-		{
-			let response = Packet::read(Pin::new(&mut read)).await?;
-			if response.get_packet_type() != TYPE_AUTH_RESPONSE {
-				return Err(UnexpectedPacket);
+		Ok(cutoff.into_response(response))
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), but fails with
+	/// [`Timeout`](enum.Error.html#variant.Timeout) if the full response has not arrived within `limit`, instead of
+	/// waiting forever on a server that silently dropped the command. The connection remains usable afterwards, any
+	/// late response is treated as an unsolicited message. The limit takes precedence over the
+	/// [`response_timeout`](struct.Settings.html#structfield.response_timeout).
+	pub async fn exec_with_timeout(&self, cmd: impl ToString, limit: Duration) -> Result<String, RconError> {
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut |_| (),
+			&mut Cutoff::default(),
+			Interrupt::timeout(limit),
+		)
+		.await
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec), but gives up with
+	/// [`Cancelled`](enum.Error.html#variant.Cancelled) once `cancel` completes, for example with the `cancelled()`
+	/// future of a `CancellationToken`. Unlike dropping the exec future, this never interrupts the connection halfway
+	/// through writing a packet, so the connection remains usable afterwards. Any late response is treated as an
+	/// unsolicited message.
+	pub async fn exec_cancellable(
+		&self, cmd: impl ToString, cancel: impl Future<Output = ()> + Send,
+	) -> Result<String, RconError> {
+		tokio::pin!(cancel);
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut |_| (),
+			&mut Cutoff::default(),
+			Interrupt::cancel(cancel),
+		)
+		.await
+	}
+
+	pub(crate) async fn exec_impl(
+		&self, cmd: &[u8], on_fragment: &mut impl FnMut(&str), cutoff: &mut Cutoff, interrupt: Interrupt<'_>,
+	) -> Result<String, RconError> {
+		let start = Instant::now();
+		let mut interrupt = interrupt.or_timeout(self.settings.response_timeout);
+		#[cfg(feature = "tracing")]
+		let span = tracing::debug_span!("rcon_exec", label = self.settings.label.as_deref().unwrap_or_default());
+		let result = self.exec_throttled(cmd, on_fragment, cutoff, &mut interrupt);
+		#[cfg(feature = "tracing")]
+		let result = tracing::Instrument::instrument(result, span);
+		let result = result.await;
+		self.record(cmd, start, &result);
+		result
+	}
+
+	/// Sends a command to the RCON server like [`exec`](#method.exec) once `at` has been reached, for commands that
+	/// have to run at a set time. With [`latency_compensation`](struct.Settings.html#structfield.latency_compensation),
+	/// the command is sent early by half the [`round_trip_time`](#method.round_trip_time). A time in the past sends the
+	/// command right away.
+	pub async fn exec_at(&self, cmd: impl ToString, at: Instant) -> Result<String, RconError> {
+		sleep_until(self.send_time(at)).await;
+		self.exec(cmd).await
+	}
+
+	/// When a command should be sent to reach the server at `at`.
+	pub(crate) fn send_time(&self, at: Instant) -> Instant {
+		match (self.settings.latency_compensation, self.round_trip_time()) {
+			(true, Some(round_trip)) => at.checked_sub(round_trip / 2).unwrap_or(at),
+			_ => at,
+		}
+	}
+
+	/// Sends a command the server never responds to, such as Factorio's `/silent-command`, for which
+	/// [`exec`](#method.exec) would wait forever. Returns the output that arrives within `grace` (such as an error
+	/// message), or an empty string right after sending if `grace` is `None`. Output arriving later is treated as an
+	/// unsolicited message.
+	pub async fn exec_no_response(&self, cmd: impl ToString, grace: Option<Duration>) -> Result<String, RconError> {
+		let cmd = cmd.to_string();
+		let start = Instant::now();
+		let result = self.exec_unanswered(cmd.as_bytes(), grace).await;
+		self.record(cmd.as_bytes(), start, &result);
+		result
+	}
+
+	async fn exec_unanswered(&self, cmd: &[u8], grace: Option<Duration>) -> Result<String, RconError> {
+		if self.settings.newline_mode == NewlineMode::Reject && cmd.contains(&b'\n') {
+			return Err(CommandContainsNewline);
+		}
+
+		if self.settings.dry_run {
+			self.rehearse(cmd)?;
+			return Ok(self.filter(String::new()));
+		}
+
+		// Without a terminator, the response can't be told complete, so whatever arrives within the grace period is it.
+		let id = self.next_counter();
+		let mut responses = self.receiver.listen(id)?;
+		self.send(id, cmd).await?;
+		let mut response = Vec::new();
+		if let Some(grace) = grace {
+			let deadline = Instant::now() + grace;
+			while let Ok(received) = timeout_at(deadline, responses.next()).await {
+				if let Received::Fragment(packet) = received? {
+					response.extend_from_slice(packet.get_body());
+				}
 			}
-			if response.get_id() == -1 {
-				return Err(PasswordIncorrect);
+		}
+
+		let response = match self.settings.lossy_utf8 {
+			true => String::from_utf8_lossy(&response).into_owned(),
+			false => String::from_utf8(response)?,
+		};
+		Ok(self.filter(response))
+	}
+
+	/// Records a finished exec in the transcript and the exec hook.
+	fn record(&self, cmd: &[u8], start: Instant, result: &Result<String, RconError>) {
+		if let Some(transcript) = &self.settings.transcript {
+			transcript.record(cmd, result);
+		}
+		if let Some(hook) = &self.settings.exec_hook {
+			hook.observe(cmd, start.elapsed(), result);
+		}
+	}
+
+	/// Applies the response filters.
+	fn filter(&self, response: String) -> String {
+		self.settings
+			.response_filters
+			.iter()
+			.fold(response, |response, filter| filter.apply(response))
+	}
+
+	/// Runs the command, sending it again while the server responds with a throttle message, up to the configured
+	/// amount of retries.
+	async fn exec_throttled(
+		&self, cmd: &[u8], on_fragment: &mut impl FnMut(&str), cutoff: &mut Cutoff, interrupt: &mut Interrupt<'_>,
+	) -> Result<String, RconError> {
+		let detector = match &self.settings.throttle_detector {
+			Some(detector) => detector,
+			None => return self.exec_lines(cmd, on_fragment, cutoff, interrupt).await,
+		};
+		let initial = cutoff.clone();
+		for _ in 0..self.settings.throttle_retries {
+			let response = self.exec_lines(cmd, on_fragment, cutoff, interrupt).await?;
+			match detector(&response) {
+				Some(retry_after) => {
+					let throttle = async {
+						sleep(retry_after).await;
+						Ok(())
+					};
+					interrupt.wait(throttle).await?
+				}
+				None => return Ok(response),
 			}
+			*cutoff = initial.clone();
+		}
+		let response = self.exec_lines(cmd, on_fragment, cutoff, interrupt).await?;
+		match detector(&response) {
+			Some(retry_after) => Err(Throttled { retry_after }),
+			None => Ok(response),
 		}
+	}
 
-		let receiver = ReceiverHandle::new(read);
+	async fn exec_lines(
+		&self, cmd: &[u8], on_fragment: &mut impl FnMut(&str), cutoff: &mut Cutoff, interrupt: &mut Interrupt<'_>,
+	) -> Result<String, RconError> {
+		let result = match self.settings.newline_mode {
+			_ if !cmd.contains(&b'\n') => self.exec_single(cmd, on_fragment, cutoff, interrupt).await?,
+			NewlineMode::Reject => return Err(CommandContainsNewline),
+			NewlineMode::Send => self.exec_single(cmd, on_fragment, cutoff, interrupt).await?,
+			NewlineMode::Split => {
+				let mut result = String::new();
+				for line in cmd.split(|b| *b == b'\n') {
+					let line = line.strip_suffix(b"\r").unwrap_or(line);
+					if !line.is_empty() {
+						result += &self.exec_single(line, on_fragment, cutoff, interrupt).await?;
+					}
+				}
+				result
+			}
+		};
 
-		Ok(Self {
-			write,
-			counter: 0,
-			receiver,
+		Ok(self.filter(result))
+	}
+
+	async fn exec_single(
+		&self, cmd: &[u8], on_fragment: &mut impl FnMut(&str), cutoff: &mut Cutoff, interrupt: &mut Interrupt<'_>,
+	) -> Result<String, RconError> {
+		let budget = match self.settings.memory_limit {
+			Some(limit) => limit.saturating_sub(self.receiver.shared.unsolicited_bytes()),
+			None => usize::MAX,
+		};
+		let mut response = Vec::new();
+		let mut exceeded = false;
+		let mut first = true;
+		self.exec_packets(cmd, interrupt, &mut |packet| {
+			let fragment = cutoff.take(packet.into_body());
+			// Once over budget, the rest of the response is still read (to keep the connection usable) but discarded
+			exceeded |= response.len() + fragment.len() > budget;
+			if !exceeded && (first || !fragment.is_empty()) {
+				on_fragment(&String::from_utf8_lossy(&fragment));
+				response.extend_from_slice(&fragment);
+			}
+			first = false;
 		})
+		.await?;
+		if exceeded {
+			return Err(MemoryLimitExceeded(budget));
+		}
+
+		match self.settings.lossy_utf8 {
+			true => Ok(String::from_utf8_lossy(&response).into_owned()),
+			false => match String::from_utf8(response) {
+				Ok(response) => Ok(response),
+				// The cutoff may have split the last character in two.
+				Err(e) if cutoff.truncated && e.utf8_error().error_len().is_none() => {
+					let valid = e.utf8_error().valid_up_to();
+					let mut response = e.into_bytes();
+					response.truncate(valid);
+					Ok(String::from_utf8(response)?)
+				}
+				Err(e) => Err(e.into()),
+			},
+		}
 	}
 
-	/// Sends a command to the RCON server, returning the combined reply (in case there are multiple packets) or an error.
-	pub async fn exec(&mut self, cmd: impl ToString) -> Result<String, RconError> {
-		// Send the original command.
-		let original_id = self.next_counter();
-		self.receiver.set_request_id(original_id);
-		Packet::new(original_id, TYPE_EXEC, cmd.to_string())
-			.send_internal(Pin::new(&mut self.write))
-			.await?;
+	/// Sends a single command, followed by the terminator once the first response packet has arrived, passing every
+	/// response packet to `on_packet` until the terminator is mirrored.
+	async fn exec_packets(
+		&self, cmd: &[u8], interrupt: &mut Interrupt<'_>, on_packet: &mut impl FnMut(Packet),
+	) -> Result<(), RconError> {
+		let mut exchange = Exchange::start(self, cmd).await?;
+		while let Some(packet) = exchange.next(interrupt).await? {
+			on_packet(packet);
+		}
+		Ok(())
+	}
 
-		// After the first read, we send an empty command, which should be mirrored.
-		// We do this because some RCON servers don't properly respond if we send execs
-		// too fast. So we wait for the first response.
-		// Our counter can never be negative due to overflow protection.
-		self.receiver.wait_for_first_packet().await?;
-		let end_id = self.next_counter();
-		Packet::new(end_id, TYPE_EXEC, "".to_string())
-			.send_internal(Pin::new(&mut self.write))
-			.await?;
+	/// Validates a command in a dry run, as it would have been when sent.
+	fn rehearse(&self, cmd: &[u8]) -> Result<(), RconError> {
+		Packet::new(0, TYPE_EXEC, cmd).create_packet_buffer()?;
+		#[cfg(feature = "tracing")]
+		tracing::debug!(len = cmd.len(), "dry run, command not sent");
+		Ok(())
+	}
+
+	/// Sends a command to the RCON server and deserializes its response as JSON, for servers, plugins or scripts
+	/// that reply in JSON. The [`response_filters`](struct.Settings.html#structfield.response_filters) are applied
+	/// before deserializing.
+	#[cfg(feature = "json")]
+	pub async fn exec_json<T: serde::de::DeserializeOwned>(&self, cmd: impl ToString) -> Result<T, RconError> {
+		Ok(serde_json::from_str(&self.exec(cmd).await?)?)
+	}
+
+	/// Sends a command to the RCON server, returning a [`ResponseStream`](struct.ResponseStream.html) that yields the
+	/// body of every response packet as it arrives, for long outputs that should be processed as they stream in
+	/// rather than held in memory in full. As the full response is never put together, the
+	/// [`response_filters`](struct.Settings.html#structfield.response_filters) and the
+	/// [`throttle_detector`](struct.Settings.html#structfield.throttle_detector) are not applied, and the exec is
+	/// neither recorded in the [`transcript`](struct.Settings.html#structfield.transcript) nor passed to the
+	/// [`exec_hook`](struct.Settings.html#structfield.exec_hook).
+	#[cfg(feature = "stream")]
+	pub fn exec_stream(&self, cmd: impl ToString) -> ResponseStream<'_> {
+		let cmd = cmd.to_string();
+		let (sender, chunks) = std::sync::mpsc::channel();
+		ResponseStream::new(async move { self.exec_chunks(cmd.as_bytes(), &sender).await }, chunks)
+	}
+
+	/// Runs a single command, passing the body of every response packet to `chunks`.
+	#[cfg(feature = "stream")]
+	pub(crate) async fn exec_chunks(
+		&self, cmd: &[u8], chunks: &std::sync::mpsc::Sender<String>,
+	) -> Result<(), RconError> {
+		let mut interrupt = Interrupt::default().or_timeout(self.settings.response_timeout);
+		let mut partial = Vec::new();
+		let mut failure = None;
+		self.exec_packets(cmd, &mut interrupt, &mut |packet| {
+			let mut bytes = mem::take(&mut partial);
+			bytes.extend_from_slice(packet.get_body());
+			// A character split over two packets is held back until the rest of it arrives
+			if let Err(e) = std::str::from_utf8(&bytes) {
+				if e.error_len().is_none() {
+					partial = bytes.split_off(e.valid_up_to());
+				}
+			}
+			let chunk = match self.settings.lossy_utf8 {
+				true => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+				false => String::from_utf8(bytes),
+			};
+			match chunk {
+				Ok(chunk) if chunk.is_empty() => (),
+				// The receiver is only gone if the stream was dropped, which also drops this exec
+				Ok(chunk) => drop(chunks.send(chunk)),
+				Err(e) => {
+					failure.get_or_insert(e);
+				}
+			}
+		})
+		.await?;
+
+		match (failure, partial.is_empty()) {
+			(Some(e), _) => Err(e.into()),
+			(None, true) => Ok(()),
+			(None, false) if self.settings.lossy_utf8 => {
+				drop(chunks.send(String::from_utf8_lossy(&partial).into_owned()));
+				Ok(())
+			}
+			(None, false) => Err(String::from_utf8(partial).unwrap_err().into()),
+		}
+	}
+
+	/// Sends a command to the RCON server, writing the body of every response packet to `writer` as it arrives, for
+	/// piping huge responses into a file, socket or compressor without putting together the full response. Returns
+	/// the amount of bytes written, after flushing the writer. Like [`exec_stream`](#method.exec_stream), the bodies
+	/// are written as received, so no [`response_filters`](struct.Settings.html#structfield.response_filters) or
+	/// UTF-8 validation are applied, and the exec is not recorded. A failing writer aborts the exec, leaving the
+	/// connection usable.
+	pub async fn exec_to_writer(&self, cmd: impl ToString, writer: impl AsyncWrite + Unpin) -> Result<u64, RconError> {
+		Ok(self.exec_into(cmd.to_string().as_bytes(), writer).await??)
+	}
+
+	/// Runs a single command, writing its response to `writer`. Failures of the writer are returned separately, as they
+	/// don't concern the connection.
+	pub(crate) async fn exec_into(
+		&self, cmd: &[u8], mut writer: impl AsyncWrite + Unpin,
+	) -> Result<std::io::Result<u64>, RconError> {
+		let mut interrupt = Interrupt::default().or_timeout(self.settings.response_timeout);
+		let mut exchange = Exchange::start(self, cmd).await?;
+		let mut written = 0;
+		while let Some(packet) = exchange.next(&mut interrupt).await? {
+			if let Err(e) = writer.write_all(packet.get_body()).await {
+				return Ok(Err(e));
+			}
+			written += packet.get_body().len() as u64;
+		}
+		Ok(writer.flush().await.map(|()| written))
+	}
 
-		self.receiver.get_response().await
+	/// Runs a list of commands one after another, returning the result of every command in the same order. With
+	/// [`BatchPolicy::StopOnError`](enum.BatchPolicy.html#variant.StopOnError), the results end at the first failure.
+	pub async fn exec_all(&self, cmds: &[impl ToString], policy: BatchPolicy) -> Vec<Result<String, RconError>> {
+		let mut results = Vec::with_capacity(cmds.len());
+		for cmd in cmds {
+			let result = self.exec(cmd.to_string()).await;
+			let failed = result.is_err();
+			results.push(result);
+			if failed && policy == BatchPolicy::StopOnError {
+				break;
+			}
+		}
+		results
+	}
+
+	/// Runs a list of commands like [`exec_all`](#method.exec_all) with
+	/// [`BatchPolicy::Continue`](enum.BatchPolicy.html#variant.Continue), but keeps up to `depth` of them in flight at
+	/// once instead of waiting for every response before sending the next command, which greatly speeds up mass
+	/// operations on servers that handle commands concurrently. The results are returned in the same order as the
+	/// commands.
+	pub async fn exec_pipelined(&self, cmds: &[impl ToString], depth: usize) -> Vec<Result<String, RconError>> {
+		let mut results: Vec<_> = cmds.iter().map(|_| None).collect();
+		let mut queue = cmds.iter().map(|cmd| cmd.to_string()).enumerate();
+		let mut in_flight = Vec::with_capacity(depth);
+		poll_fn(|cx| loop {
+			while in_flight.len() < depth.max(1) {
+				match queue.next() {
+					Some((i, cmd)) => in_flight.push((i, Box::pin(self.exec(cmd)))),
+					None => break,
+				}
+			}
+			if in_flight.is_empty() {
+				return Poll::Ready(());
+			}
+
+			let before = in_flight.len();
+			in_flight.retain_mut(|(i, exec)| match exec.as_mut().poll(cx) {
+				Poll::Ready(result) => {
+					results[*i] = Some(result);
+					false
+				}
+				Poll::Pending => true,
+			});
+			// Otherwise room was freed up for the next commands
+			if in_flight.len() == before {
+				return Poll::Pending;
+			}
+		})
+		.await;
+		results
+			.into_iter()
+			.map(|result| result.expect("every command ran"))
+			.collect()
+	}
+
+	/// Runs a paginated command for servers that truncate long outputs, concatenating the responses of all pages.
+	/// `command` builds the command for a page (starting at 1), and `is_last_page` inspects the response of a page to
+	/// decide whether to stop. Pagination also stops once a page is empty or repeats the previous one, as many
+	/// servers respond to out-of-range page numbers that way.
+	pub async fn paginate(
+		&self, mut command: impl FnMut(usize) -> String, mut is_last_page: impl FnMut(usize, &str) -> bool,
+	) -> Result<String, RconError> {
+		let mut result = String::new();
+		let mut previous = String::new();
+		for page in 1.. {
+			let response = self.exec(command(page)).await?;
+			if response.is_empty() || response == previous {
+				break;
+			}
+			result += &response;
+			if is_last_page(page, &response) {
+				break;
+			}
+			previous = response;
+		}
+		Ok(result)
 	}
 
 	/// Closes the connection, joining any background tasks that were spawned to help manage it.
@@ -129,9 +1134,152 @@ impl SingleConnection {
 		self.receiver.close().await;
 	}
 
-	fn next_counter(&mut self) -> i32 {
-		self.counter = next_counter(self.counter);
-		self.counter
+	/// Shuts down the background receiver and returns the underlying `TcpStream`, so it can be handed to other
+	/// protocol code after authenticating. Any response data that has not been received yet is discarded, so this
+	/// should only be called while no exec is in progress.
+	#[doc(alias = "into_inner")]
+	pub async fn into_stream(self) -> Result<TcpStream, RconError> {
+		let read = self.receiver.shutdown().await?;
+		Ok(read
+			.reunite(self.writer.into_inner().write)
+			.expect("read and write halves originate from the same stream"))
+	}
+
+	/// Shuts the connection down once `signal` completes, for applications that use a global shutdown signal such
+	/// as `CancellationToken::cancelled_owned` or `tokio::signal::ctrl_c`. Commands in flight and any sent afterwards
+	/// fail with [`ConnectionClosing`](enum.Error.html#variant.ConnectionClosing). The returned handle completes once
+	/// the background tasks have been stopped and joined.
+	pub fn shutdown_signal(&self, signal: impl Future<Output = ()> + Send + 'static) -> JoinHandle<()> {
+		self.receiver.shutdown_on(signal)
+	}
+
+	#[cfg(feature = "reconnection")]
+	pub(crate) fn shutdown_handle(&self) -> ShutdownHandle {
+		self.receiver.shutdown_handle()
+	}
+
+	pub(crate) fn settings(&self) -> &Settings {
+		&self.settings
+	}
+
+	/// Returns the capabilities of this connection, as determined by the settings it was opened with.
+	pub fn capabilities(&self) -> Capabilities {
+		Capabilities::new(&self.settings)
+	}
+
+	/// Returns counters of the packets this connection has received outside of a request.
+	pub fn stats(&self) -> Stats {
+		let shared = &self.receiver.shared;
+		Stats {
+			keepalive_frames: shared.keepalive_frames.load(Ordering::Relaxed),
+			unsolicited_packets: shared.unsolicited_packets.load(Ordering::Relaxed),
+			discarded_packets: shared.discarded_packets.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Returns the address of the server this connection was made to, which is the one that was used out of all the
+	/// addresses the address given to [`open`](#method.open) resolved to.
+	pub fn peer_addr(&self) -> SocketAddr {
+		self.peer_addr
+	}
+
+	/// Returns the local address of this connection.
+	pub fn local_addr(&self) -> SocketAddr {
+		self.local_addr
+	}
+
+	/// Returns when this connection was authenticated, to tell how long the session has been up.
+	pub fn connected_since(&self) -> Instant {
+		self.connected_since
+	}
+
+	/// Returns the time it takes the server to respond to a command, measured from sending a command until the first
+	/// packet of its response arrives and smoothed over recent commands. `None` until the first response arrived.
+	pub fn round_trip_time(&self) -> Option<Duration> {
+		match self.round_trip.load(Ordering::Relaxed) {
+			0 => None,
+			nanos => Some(Duration::from_nanos(nanos)),
+		}
+	}
+
+	/// Measures the round-trip time to the server by running the [`terminator`](struct.Settings.html#structfield.terminator)
+	/// (an empty command by default), which every server answers without doing anything, for health dashboards or to
+	/// pick the closest of several servers. The measurement is added to the
+	/// [`round_trip_time`](#method.round_trip_time), but not recorded in the
+	/// [`transcript`](struct.Settings.html#structfield.transcript) or passed to the
+	/// [`exec_hook`](struct.Settings.html#structfield.exec_hook). In a [`dry_run`](struct.Settings.html#structfield.dry_run),
+	/// it returns zero.
+	pub async fn ping(&self) -> Result<Duration, RconError> {
+		let mut interrupt = Interrupt::default().or_timeout(self.settings.response_timeout);
+		let mut exchange = Exchange::start(self, self.settings.terminator.as_bytes()).await?;
+		while exchange.next(&mut interrupt).await?.is_some() {}
+		Ok(exchange.round_trip.unwrap_or_default())
+	}
+
+	/// Adds a measured round-trip time to the average, weighing it like TCP does.
+	fn measure_round_trip(&self, sample: Duration) {
+		let sample = (sample.as_nanos().min(u64::MAX as u128) as u64).max(1);
+		let _ = self
+			.round_trip
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |smoothed| match smoothed {
+				0 => Some(sample),
+				smoothed => Some(smoothed - smoothed / 8 + sample / 8),
+			});
+	}
+
+	/// Waits until there are [`unsolicited`](struct.Settings.html#structfield.unsolicited) messages to drain. Never
+	/// completes if no buffer is configured.
+	pub(crate) async fn unsolicited_arrived(&self) {
+		self.receiver.shared.unsolicited_arrived.notified().await
+	}
+
+	/// Returns the [`unsolicited`](struct.Settings.html#structfield.unsolicited) messages received since the last
+	/// call, oldest first. Always empty if no buffer is configured.
+	pub fn drain_unsolicited(&self) -> Result<Vec<String>, RconError> {
+		let mut unsolicited = match &self.receiver.shared.unsolicited {
+			Some(unsolicited) => unsolicited.lock().unwrap_or_else(|e| e.into_inner()),
+			None => return Ok(Vec::new()),
+		};
+		if unsolicited.dropped > 0 {
+			return Err(UnsolicitedOverflow(mem::take(&mut unsolicited.dropped)));
+		}
+		unsolicited.bytes = 0;
+		Ok(unsolicited.messages.drain(..).collect())
+	}
+
+	/// Sends a command packet encoded into the send buffer of the connection, first waiting out the
+	/// [`packet_pacing`](struct.Settings.html#structfield.packet_pacing).
+	async fn send(&self, id: i32, cmd: &[u8]) -> Result<(), RconError> {
+		let mut writer = self.writer.lock().await;
+		let writer = &mut *writer;
+		writer.buf.clear();
+		Packet::encode(id, TYPE_EXEC, cmd, &mut writer.buf)?;
+		if let (Some(pacing), Some(last_sent)) = (self.settings.packet_pacing, writer.last_sent) {
+			sleep_until(last_sent + pacing).await;
+		}
+		// If a previous exec was cancelled while writing, the stream may contain half a packet, which would make the
+		// server misinterpret everything sent after it.
+		if writer.writing {
+			return Err(PartialWrite);
+		}
+		writer.writing = true;
+		let result = async {
+			writer.write.write_all(&writer.buf).await?;
+			Ok(writer.write.flush().await?)
+		}
+		.await;
+		writer.writing = false;
+		writer.last_sent = Some(Instant::now());
+		result
+	}
+
+	fn next_counter(&self) -> i32 {
+		let previous = self
+			.counter
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |counter| {
+				Some(next_counter(counter))
+			});
+		next_counter(previous.unwrap_or_else(|counter| counter))
 	}
 }
 
@@ -139,64 +1287,240 @@ fn next_counter(counter: i32) -> i32 {
 	counter.checked_add(1).unwrap_or(1)
 }
 
+/// Response of [`exec_with_cutoff`](struct.Connection.html#method.exec_with_cutoff).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CutoffResponse {
+	/// The collected part of the response.
+	pub response: String,
+	/// Whether the response was longer than the cutoff, meaning the rest of it was discarded.
+	pub truncated: bool,
+}
+
+/// Response of [`exec_detailed`](struct.Connection.html#method.exec_detailed), along with how it was exchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecDetails {
+	/// The combined response, after the [`response_filters`](struct.Settings.html#structfield.response_filters).
+	pub response: String,
+	/// Time between sending the command and receiving the end of the response.
+	pub latency: Duration,
+	/// The amount of response packets the response was made up of.
+	pub packets: usize,
+	/// The packet ID the command was sent with.
+	pub request_id: i32,
+	/// The packet ID the terminator was sent with, or `None` in a
+	/// [`dry_run`](struct.Settings.html#structfield.dry_run).
+	pub end_id: Option<i32>,
+}
+
+/// Remaining amount of response bytes an exec may collect.
+#[derive(Clone)]
+pub(crate) struct Cutoff {
+	remaining: usize,
+	truncated: bool,
+}
+
+impl Cutoff {
+	pub(crate) fn new(max_bytes: usize) -> Self {
+		Self {
+			remaining: max_bytes,
+			truncated: false,
+		}
+	}
+
+	pub(crate) fn into_response(self, response: String) -> CutoffResponse {
+		CutoffResponse {
+			response,
+			truncated: self.truncated,
+		}
+	}
+
+	/// Returns the part of the fragment that fits within the cutoff.
+	fn take(&mut self, mut fragment: Vec<u8>) -> Vec<u8> {
+		if fragment.len() > self.remaining {
+			fragment.truncate(self.remaining);
+			self.truncated = true;
+		}
+		self.remaining -= fragment.len();
+		fragment
+	}
+}
+
+impl Default for Cutoff {
+	fn default() -> Self {
+		Self::new(usize::MAX)
+	}
+}
+
+/// Ways to end an exec early. These only take effect while waiting on the server, never while a packet is being
+/// written, so an interrupted exec can't leave half a packet on the stream.
+#[derive(Default)]
+pub(crate) struct Interrupt<'a> {
+	// The time limit, and the moment it expires
+	deadline: Option<(Duration, Instant)>,
+	cancel: Option<Pin<&'a mut (dyn Future<Output = ()> + Send)>>,
+}
+
+impl<'a> Interrupt<'a> {
+	pub(crate) fn timeout(limit: Duration) -> Self {
+		Self {
+			deadline: Some((limit, Instant::now() + limit)),
+			cancel: None,
+		}
+	}
+
+	pub(crate) fn cancel(cancel: Pin<&'a mut (dyn Future<Output = ()> + Send)>) -> Self {
+		Self {
+			deadline: None,
+			cancel: Some(cancel),
+		}
+	}
+
+	/// Applies the default time limit, unless one was set already.
+	fn or_timeout(mut self, limit: Option<Duration>) -> Self {
+		if self.deadline.is_none() {
+			self.deadline = limit.map(|limit| (limit, Instant::now() + limit));
+		}
+		self
+	}
+
+	/// Awaits the future, unless the exec is interrupted first.
+	async fn wait<T>(&mut self, future: impl Future<Output = Result<T, RconError>>) -> Result<T, RconError> {
+		let deadline = self.deadline;
+		let expired = async move {
+			match deadline {
+				Some((limit, at)) => {
+					sleep_until(at).await;
+					limit
+				}
+				None => pending().await,
+			}
+		};
+		let cancelled = async {
+			match &mut self.cancel {
+				Some(cancel) => cancel.await,
+				None => pending().await,
+			}
+		};
+		select! {
+			result = future => result,
+			limit = expired => Err(Timeout(limit)),
+			_ = cancelled => Err(Cancelled),
+		}
+	}
+}
+
 struct ReceiverHandle {
 	shared: Arc<ReceiverHandleShared>,
-	receiver: mpsc::Receiver<Result<String, RconError>>,
-	task: Option<JoinHandle<()>>,
+	// Taken by whoever shuts the receiving task down, which is either close or a shutdown signal
+	task: Arc<std::sync::Mutex<Option<JoinHandle<OwnedReadHalf>>>>,
 }
 
 impl ReceiverHandle {
-	pub fn new(stream: OwnedReadHalf) -> Self {
+	pub fn new(stream: OwnedReadHalf, settings: &Settings) -> Self {
 		let shared = Arc::new(ReceiverHandleShared {
-			request_id: AtomicI32::new(-1),
-			received_first_response: Notify::new(),
+			requests: std::sync::Mutex::new(Requests {
+				pending: HashMap::new(),
+				failure: None,
+			}),
 			close_connection: Notify::new(),
+			source_quirks: settings.source_quirks,
+			dedup_responses: settings.dedup_responses,
+			single_session: settings.single_session,
+			max_packet_size: settings.max_packet_size(),
+			unsolicited: settings.unsolicited.clone().map(|config| {
+				std::sync::Mutex::new(Unsolicited {
+					config,
+					messages: VecDeque::new(),
+					bytes: 0,
+					memory_limit: settings.memory_limit.unwrap_or(usize::MAX),
+					dropped: 0,
+				})
+			}),
+			unsolicited_arrived: Notify::new(),
+			keepalive_frames: AtomicU64::new(0),
+			unsolicited_packets: AtomicU64::new(0),
+			discarded_packets: AtomicU64::new(0),
 		});
-		let (sender, receiver) = mpsc::channel(1);
-		let task = tokio::spawn(receive_loop(stream, shared.clone(), sender));
+		let task = tokio::spawn(receive_loop(stream, shared.clone()));
 		Self {
 			shared,
-			receiver,
-			task: Some(task),
+			task: Arc::new(std::sync::Mutex::new(Some(task))),
+		}
+	}
+
+	/// Starts listening for the response to the command with the given packet id, which has to happen before the
+	/// command is sent.
+	fn listen(&self, id: i32) -> Result<PendingResponse, RconError> {
+		let mut requests = self.shared.requests();
+		if let Some(failure) = &requests.failure {
+			return Err(failure.to_error());
 		}
+		let (sender, receiver) = mpsc::channel(1);
+		requests.pending.insert(
+			id,
+			Pending {
+				end_id: None,
+				sender,
+				previous: None,
+			},
+		);
+		Ok(PendingResponse {
+			id,
+			shared: self.shared.clone(),
+			receiver,
+		})
 	}
 
-	pub fn set_request_id(&mut self, id: i32) {
-		self.shared.request_id.store(id, Ordering::Release);
+	async fn close(self) {
+		let _ = self.shutdown().await;
 	}
 
-	pub async fn wait_for_first_packet(&mut self) -> Result<(), RconError> {
-		select! {
-			_ = self.shared.received_first_response.notified() => Ok(()),
-			result = Self::get_response_impl(&mut self.receiver) => match result {
-				Ok(_) => unreachable!(), // Background task won't return a response until after the first packet is received
-				Err(e) => Err(e),
-			}
-		}
+	/// Stops the receiving task, returning the read half it was using.
+	async fn shutdown(&self) -> Result<OwnedReadHalf, RconError> {
+		shutdown(&self.shared, &self.task).await
 	}
 
-	async fn get_response(&mut self) -> Result<String, RconError> {
-		Self::get_response_impl(&mut self.receiver).await
+	/// Stops the receiving task once `signal` completes.
+	fn shutdown_on(&self, signal: impl Future<Output = ()> + Send + 'static) -> JoinHandle<()> {
+		let handle = self.shutdown_handle();
+		tokio::spawn(async move {
+			signal.await;
+			handle.shutdown().await;
+		})
 	}
 
-	async fn get_response_impl(receiver: &mut mpsc::Receiver<Result<String, RconError>>) -> Result<String, RconError> {
-		match receiver.recv().await {
-			Some(val) => val,
-			None => Err(RconError::IO(std::io::Error::new(
-				ErrorKind::ConnectionReset,
-				"receiving task terminated",
-			))),
+	fn shutdown_handle(&self) -> ShutdownHandle {
+		ShutdownHandle {
+			shared: self.shared.clone(),
+			task: self.task.clone(),
 		}
 	}
+}
 
-	async fn close(mut self) {
-		if let Some(task) = self.task.take() {
-			self.shared.close_connection.notify_one();
-			let _ = task.await;
-		}
+/// Stops the receiving task of a connection without access to the connection itself, such as while an exec is using it.
+#[derive(Clone)]
+pub(crate) struct ShutdownHandle {
+	shared: Arc<ReceiverHandleShared>,
+	task: Arc<std::sync::Mutex<Option<JoinHandle<OwnedReadHalf>>>>,
+}
+
+impl ShutdownHandle {
+	/// Stops the receiving task, failing the commands in flight with
+	/// [`ConnectionClosing`](enum.Error.html#variant.ConnectionClosing).
+	pub(crate) async fn shutdown(&self) {
+		let _ = shutdown(&self.shared, &self.task).await;
 	}
 }
 
+async fn shutdown(
+	shared: &ReceiverHandleShared, task: &std::sync::Mutex<Option<JoinHandle<OwnedReadHalf>>>,
+) -> Result<OwnedReadHalf, RconError> {
+	let task = task.lock().unwrap_or_else(|e| e.into_inner()).take();
+	let task = task.ok_or(ConnectionClosing)?;
+	shared.close_connection.notify_one();
+	task.await.map_err(|e| RconError::IO(std::io::Error::other(e)))
+}
+
 impl Drop for ReceiverHandle {
 	fn drop(&mut self) {
 		self.shared.close_connection.notify_one();
@@ -204,106 +1528,457 @@ impl Drop for ReceiverHandle {
 }
 
 struct ReceiverHandleShared {
-	request_id: AtomicI32,
-	received_first_response: Notify,
+	requests: std::sync::Mutex<Requests>,
 	close_connection: Notify,
+	source_quirks: bool,
+	dedup_responses: bool,
+	single_session: bool,
+	max_packet_size: usize,
+	unsolicited: Option<std::sync::Mutex<Unsolicited>>,
+	// Notified whenever the unsolicited buffer has something to drain
+	unsolicited_arrived: Notify,
+	keepalive_frames: AtomicU64,
+	unsolicited_packets: AtomicU64,
+	discarded_packets: AtomicU64,
 }
 
-#[derive(Debug)]
-enum ReceiveError {
-	Rcon(RconError),
-	Shutdown,
-}
+impl ReceiverHandleShared {
+	fn requests(&self) -> std::sync::MutexGuard<'_, Requests> {
+		self.requests.lock().unwrap_or_else(|e| e.into_inner())
+	}
 
-impl Display for ReceiveError {
-	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-		match self {
-			Self::Rcon(e) => e.fmt(f),
-			Self::Shutdown => write!(f, "receiver task terminated"),
+	/// Finds the request a packet belongs to, returning where to send it and what to send, if anything.
+	fn route(&self, packet: Packet) -> Option<(ResponseSender, Result<Received, RconError>)> {
+		// Source engine servers may trail fragmented responses with a marker packet with a body of 0x00 0x01 (plus
+		// the regular two null terminators), which is not part of the actual response. It carries the ID of the
+		// terminator, so it usually arrives after the command has already finished.
+		if self.source_quirks && packet.get_body() == [0x00, 0x01] {
+			return None;
 		}
+
+		let mut requests = self.requests();
+		let id = packet.get_id();
+
+		// Check if the ID belongs to a command in flight, either as its response or as the mirrored terminator. If
+		// not, the server either sent it on its own accord or is buggy or non-conformant.
+		let original_id = match requests.pending.contains_key(&id) {
+			true => id,
+			false => match requests.pending.iter().find(|(_, pending)| pending.end_id == Some(id)) {
+				Some((original_id, _)) => *original_id,
+				None => {
+					drop(requests);
+					self.push_unsolicited(packet);
+					return None;
+				}
+			},
+		};
+
+		// We should only be receiving a response at this time.
+		if packet.get_packet_type() != TYPE_RESPONSE {
+			let pending = requests.pending.remove(&original_id)?;
+			let body = String::from_utf8_lossy(packet.get_body()).into_owned();
+			return Some((pending.sender, Err(UnexpectedPacket(body))));
+		}
+
+		// If we receive a response to our terminator, that means all previous messages have been sent and
+		// (hopefully) received. That means the command is finished.
+		if id != original_id {
+			let pending = requests.pending.remove(&original_id)?;
+			return Some((pending.sender, Ok(Received::End)));
+		}
+
+		// Skip exact duplicates of the previous packet, if requested.
+		let pending = requests.pending.get_mut(&original_id)?;
+		if self.dedup_responses {
+			let previous = (id, packet.get_body().to_vec());
+			if pending.previous.as_ref() == Some(&previous) {
+				return None;
+			}
+			pending.previous = Some(previous);
+		}
+
+		// All checks have passed; pass the body on to be appended to the end result.
+		Some((pending.sender.clone(), Ok(Received::Fragment(packet))))
 	}
-}
 
-impl std::error::Error for ReceiveError {}
+	/// Fails every command in flight, as well as any sent after it, once the connection can no longer be read from.
+	fn fail(&self, error: RconError) {
+		let failure = Failure::new(error);
+		let mut requests = self.requests();
+		for (_, pending) in requests.pending.drain() {
+			let _ = pending.sender.try_send(Err(failure.to_error()));
+		}
+		requests.failure = Some(failure);
+	}
 
-impl From<RconError> for ReceiveError {
-	fn from(e: RconError) -> Self {
-		Self::Rcon(e)
+	/// Handles a packet that did not belong to the current request, buffering it if configured to do so.
+	fn push_unsolicited(&self, packet: Packet) {
+		// Some servers periodically send empty packets as keepalives, which carry nothing worth keeping.
+		if packet.get_body().is_empty() {
+			self.keepalive_frames.fetch_add(1, Ordering::Relaxed);
+			return;
+		}
+		self.unsolicited_packets.fetch_add(1, Ordering::Relaxed);
+
+		let mut unsolicited = match &self.unsolicited {
+			Some(unsolicited) => unsolicited.lock().unwrap_or_else(|e| e.into_inner()),
+			None => return self.discard(&packet, "no unsolicited buffer is configured"),
+		};
+		let message = String::from_utf8_lossy(packet.get_body()).into_owned();
+		while unsolicited.messages.len() >= unsolicited.config.capacity
+			|| unsolicited.bytes + message.len() > unsolicited.memory_limit
+		{
+			match unsolicited.config.overflow {
+				OverflowPolicy::DropOldest => match unsolicited.messages.pop_front() {
+					Some(oldest) => {
+						unsolicited.bytes -= oldest.len();
+						self.discard(&packet, "the oldest buffered message was dropped to make room");
+					}
+					None => return self.discard(&packet, "it exceeds the memory limit"),
+				},
+				OverflowPolicy::DropNewest => return self.discard(&packet, "the unsolicited buffer is full"),
+				OverflowPolicy::Error => {
+					unsolicited.dropped += 1;
+					self.unsolicited_arrived.notify_one();
+					return self.discard(&packet, "the unsolicited buffer is full");
+				}
+			}
+		}
+		unsolicited.bytes += message.len();
+		unsolicited.messages.push_back(message);
+		self.unsolicited_arrived.notify_one();
+	}
+
+	/// Counts a packet (or with DropOldest, the message it replaced) that won't be handed out, which would otherwise go
+	/// unnoticed.
+	#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+	fn discard(&self, packet: &Packet, reason: &str) {
+		self.discarded_packets.fetch_add(1, Ordering::Relaxed);
+		#[cfg(feature = "tracing")]
+		tracing::debug!(
+			packet_id = packet.get_id(),
+			len = packet.get_body().len(),
+			reason,
+			"discarded unsolicited packet"
+		);
+	}
+
+	/// Returns the amount of bytes held by the unsolicited message buffer.
+	fn unsolicited_bytes(&self) -> usize {
+		match &self.unsolicited {
+			Some(unsolicited) => unsolicited.lock().unwrap_or_else(|e| e.into_inner()).bytes,
+			None => 0,
+		}
 	}
 }
 
-async fn receive_loop(
-	mut stream: OwnedReadHalf, shared: Arc<ReceiverHandleShared>, sender: mpsc::Sender<Result<String, RconError>>,
-) {
-	loop {
-		let response = receive_response(Pin::new(&mut stream), &shared).await;
-		shared.request_id.store(-1, Ordering::Release);
-		let response = match response {
-			Ok(r) => Ok(r),
-			Err(e) => match e {
-				ReceiveError::Rcon(r) => Err(r),
-				ReceiveError::Shutdown => return,
+struct Unsolicited {
+	config: UnsolicitedBuffer,
+	messages: VecDeque<String>,
+	// Combined length of the messages
+	bytes: usize,
+	memory_limit: usize,
+	// Messages dropped under OverflowPolicy::Error since the last drain
+	dropped: usize,
+}
+
+/// Messages sent by the receiving task for the response to the current request.
+enum Received {
+	/// A single response packet.
+	Fragment(Packet),
+	/// The terminator has been mirrored, so the response is complete.
+	End,
+}
+
+/// Commands in flight, keyed by the packet ID of the command.
+struct Requests {
+	pending: HashMap<i32, Pending>,
+	// Set once reading from the connection failed
+	failure: Option<Failure>,
+}
+
+type ResponseSender = mpsc::Sender<Result<Received, RconError>>;
+
+struct Pending {
+	// Set right before the terminator is sent
+	end_id: Option<i32>,
+	sender: ResponseSender,
+	// The previous packet, for dedup_responses
+	previous: Option<(i32, Vec<u8>)>,
+}
+
+/// The error that stopped the receiving task, which is handed out to every command that is sent afterwards.
+struct Failure {
+	kind: ErrorKind,
+	message: String,
+	cause: FailureCause,
+}
+
+/// What stopped the receiving task, as the errors handed out for some causes are not IO errors.
+enum FailureCause {
+	Io,
+	Superseded,
+	Panic,
+	Closing,
+}
+
+impl Failure {
+	fn new(error: RconError) -> Self {
+		match error {
+			IO(e) => Self {
+				kind: e.kind(),
+				message: e.to_string(),
+				cause: FailureCause::Io,
 			},
-		};
-		let _ = sender.send(response).await;
+			Superseded => Self {
+				kind: ErrorKind::ConnectionAborted,
+				message: Superseded.to_string(),
+				cause: FailureCause::Superseded,
+			},
+			InternalError(message) => Self {
+				kind: ErrorKind::Other,
+				message,
+				cause: FailureCause::Panic,
+			},
+			ConnectionClosing => Self {
+				kind: ErrorKind::ConnectionAborted,
+				message: ConnectionClosing.to_string(),
+				cause: FailureCause::Closing,
+			},
+			e => Self {
+				kind: ErrorKind::InvalidData,
+				message: e.to_string(),
+				cause: FailureCause::Io,
+			},
+		}
 	}
-}
 
-async fn receive_response(
-	mut stream: Pin<&mut impl AsyncRead>, shared: &ReceiverHandleShared,
-) -> Result<String, ReceiveError> {
-	let mut end_id = -1;
-	let mut result = String::new();
+	fn to_error(&self) -> RconError {
+		match self.cause {
+			FailureCause::Io => IO(std::io::Error::new(self.kind, self.message.clone())),
+			FailureCause::Superseded => Superseded,
+			FailureCause::Panic => InternalError(self.message.clone()),
+			FailureCause::Closing => ConnectionClosing,
+		}
+	}
+}
 
-	// Loop until we have confirmation the message is complete.
-	loop {
-		// Read the first response to the command.
-		let response = select! {
-			packet = Packet::read(stream.as_mut()) => packet.map_err(ReceiveError::Rcon),
-			_ = shared.close_connection.notified() => Err(ReceiveError::Shutdown),
-		}?;
+/// A single command in flight, handing out its response packet by packet.
+struct Exchange<'a> {
+	connection: &'a SingleConnection,
+	id: i32,
+	// When the command was sent, and how long it took for the first response packet to arrive
+	sent: Instant,
+	round_trip: Option<Duration>,
+	// Unset in a dry run, or once the response is complete
+	responses: Option<PendingResponse>,
+	// Set once the terminator has been sent
+	end_id: Option<i32>,
+}
 
-		let original_id = shared.request_id.load(Ordering::Acquire);
-		if original_id <= 0 {
-			// Not currently listening for a response.
-			// (SingleConnection always uses a positive counter.)
-			continue;
+impl<'a> Exchange<'a> {
+	/// Sends the command, listening for its response before it can possibly arrive.
+	async fn start(connection: &'a SingleConnection, cmd: &[u8]) -> Result<Exchange<'a>, RconError> {
+		if connection.settings.newline_mode == NewlineMode::Reject && cmd.contains(&b'\n') {
+			return Err(CommandContainsNewline);
+		}
+		if connection.settings.dry_run {
+			connection.rehearse(cmd)?;
+			return Ok(Self {
+				connection,
+				id: 0,
+				sent: Instant::now(),
+				round_trip: None,
+				responses: None,
+				end_id: None,
+			});
 		}
 
-		// Check if we received the correct ID. If not, either the client or server is buggy or non-conformant.
-		// Either way, we'll skip the packet.
-		if response.get_id() != original_id && response.get_id() != end_id {
-			continue;
+		let original_id = connection.next_counter();
+		let responses = connection.receiver.listen(original_id)?;
+		connection.send(original_id, cmd).await?;
+		#[cfg(feature = "tracing")]
+		tracing::debug!(packet_id = original_id, len = cmd.len(), "sent command");
+		Ok(Self {
+			connection,
+			id: original_id,
+			sent: Instant::now(),
+			round_trip: None,
+			responses: Some(responses),
+			end_id: None,
+		})
+	}
+
+	/// Waits for the next response packet, returning `None` once the response is complete.
+	async fn next(&mut self, interrupt: &mut Interrupt<'_>) -> Result<Option<Packet>, RconError> {
+		let responses = match &mut self.responses {
+			Some(responses) => responses,
+			None => return Ok(None),
+		};
+		let packet = match interrupt.wait(responses.next()).await? {
+			Received::Fragment(packet) => packet,
+			Received::End => {
+				#[cfg(feature = "tracing")]
+				tracing::debug!(packet_id = self.id, end_packet_id = self.end_id, "received response");
+				self.responses = None;
+				return Ok(None);
+			}
+		};
+
+		// After the first read, we send the terminator (an empty command by default), which should be mirrored.
+		// We do this because some RCON servers don't properly respond if we send execs
+		// too fast. So we wait for the first response.
+		// Our counter can never be negative due to overflow protection.
+		if self.end_id.is_none() {
+			let round_trip = self.sent.elapsed();
+			self.round_trip = Some(round_trip);
+			self.connection.measure_round_trip(round_trip);
+			let end_id = self.connection.next_counter();
+			responses.expect_end(end_id);
+			let terminator = self.connection.settings.terminator.as_str();
+			self.connection.send(end_id, terminator.as_bytes()).await?;
+			self.end_id = Some(end_id);
+			#[cfg(feature = "tracing")]
+			tracing::trace!(packet_id = end_id, "sent terminator");
+		} else {
+			#[cfg(feature = "tracing")]
+			tracing::trace!(packet_id = self.id, len = packet.get_body().len(), "received fragment");
 		}
+		Ok(Some(packet))
+	}
+}
 
-		// We should only be receiving a response at this time.
-		if response.get_packet_type() != TYPE_RESPONSE {
-			return Err(ReceiveError::from(UnexpectedPacket));
+/// The receiving end of a single command in flight, which stops listening for its response once dropped.
+struct PendingResponse {
+	id: i32,
+	shared: Arc<ReceiverHandleShared>,
+	receiver: mpsc::Receiver<Result<Received, RconError>>,
+}
+
+impl PendingResponse {
+	/// Registers the packet ID of the terminator, which has to happen before it is sent.
+	fn expect_end(&self, end_id: i32) {
+		if let Some(pending) = self.shared.requests().pending.get_mut(&self.id) {
+			pending.end_id = Some(end_id);
 		}
+	}
 
-		// Let the sending task send the empty command.
-		if end_id == -1 {
-			end_id = next_counter(original_id);
-			shared.received_first_response.notify_one();
+	async fn next(&mut self) -> Result<Received, RconError> {
+		match self.receiver.recv().await {
+			Some(val) => val,
+			None => Err(RconError::IO(std::io::Error::new(
+				ErrorKind::ConnectionReset,
+				"receiving task terminated",
+			))),
 		}
+	}
+}
 
-		// If we receive a response to our empty command, that means all
-		// previous messages have been sent and (hopefully) received. That means
-		// we can finish up our result.
-		if response.get_id() == end_id {
-			break;
+impl Drop for PendingResponse {
+	fn drop(&mut self) {
+		self.shared.requests().pending.remove(&self.id);
+	}
+}
+
+async fn receive_loop(mut stream: OwnedReadHalf, shared: Arc<ReceiverHandleShared>) -> OwnedReadHalf {
+	// A panic would otherwise leave every command in flight waiting for a response that never comes
+	if let Err(message) = catch_unwind(receive(&mut stream, &shared)).await {
+		shared.fail(InternalError(message));
+		shared.close_connection.notified().await;
+	}
+	// Anything in flight or sent from now on would never receive a response, unless reading already failed
+	let closing = shared.requests().failure.is_none();
+	if closing {
+		shared.fail(ConnectionClosing);
+	}
+	stream
+}
+
+/// Routes the packets read from the connection to the commands they belong to, until the connection is closed.
+async fn receive(stream: &mut OwnedReadHalf, shared: &ReceiverHandleShared) {
+	loop {
+		let packet = select! {
+			packet = Packet::read(Pin::new(&mut *stream), shared.max_packet_size) => packet,
+			_ = shared.close_connection.notified() => break,
+		};
+		match packet {
+			Ok(packet) => {
+				if let Some((sender, message)) = shared.route(packet) {
+					// The caller may have given up waiting, in which case the message is simply discarded.
+					let _ = sender.send(message).await;
+				}
+			}
+			Err(e) => {
+				// A server allowing a single client closes the connection once another client connects
+				let e = match e {
+					IO(e) if e.kind() == ErrorKind::UnexpectedEof && shared.single_session => {
+						match shared.requests().pending.is_empty() {
+							true => Superseded,
+							false => IO(e),
+						}
+					}
+					e => e,
+				};
+				shared.fail(e);
+				shared.close_connection.notified().await;
+				break;
+			}
 		}
+	}
+}
+
+async fn authenticate(
+	read: &mut OwnedReadHalf, write: &mut OwnedWriteHalf, pass: String, max_len: usize,
+) -> Result<(), RconError> {
+	Packet::new(0, TYPE_AUTH, pass).send_internal(Pin::new(write)).await?;
 
-		// All checks have passed; append body to the end result.
-		result += response.get_body();
+	// Servers may send response packets ahead of the auth response, which can contain the reason of a rejection
+	// (such as "banned" or "too many connections"), so we keep those around.
+	let mut messages = Vec::new();
+	loop {
+		let response = match Packet::read(Pin::new(&mut *read), max_len).await {
+			Ok(response) => response,
+			// Minecraft and some others hang up on a wrong password instead of responding with an id of -1.
+			Err(IO(e)) if matches!(e.kind(), ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset) => {
+				return Err(rejection(messages));
+			}
+			Err(e) => return Err(e),
+		};
+		let body = String::from_utf8_lossy(response.get_body()).into_owned();
+		match response.get_packet_type() {
+			TYPE_RESPONSE => messages.push(body),
+			TYPE_AUTH_RESPONSE if response.get_id() == -1 => {
+				messages.push(body);
+				return Err(rejection(messages));
+			}
+			TYPE_AUTH_RESPONSE => return Ok(()),
+			_ => return Err(UnexpectedPacket(body)),
+		}
 	}
+}
 
-	Ok(result)
+/// Builds the error for a rejected password out of the messages the server sent along with it.
+fn rejection(mut messages: Vec<String>) -> RconError {
+	messages.retain(|message| !message.is_empty());
+	PasswordIncorrect(messages.join("\n"))
 }
 
-async fn try_connect(address: impl ToSocketAddrs, timeout_duration: Duration) -> Result<TcpStream, RconError> {
+async fn try_connect(address: impl ToSocketAddrs, settings: &Settings) -> Result<TcpStream, RconError> {
 	// Resolve the host
-	let mut addrs: Vec<SocketAddr> = lookup_host(address).await?.collect();
+	let mut addrs: Vec<SocketAddr> = lookup_host(address)
+		.await
+		.map_err(|e| IO(std::io::Error::new(ErrorKind::AddrNotAvailable, e)))?
+		.collect();
+	if let Some(filter) = &settings.address_filter {
+		let resolved = addrs.len();
+		addrs.retain(|addr| filter(addr));
+		if resolved > 0 && addrs.is_empty() {
+			return Err(IO(std::io::Error::new(
+				ErrorKind::PermissionDenied,
+				"No resolved rcon host addr passed the address filter",
+			)));
+		}
+	}
 	// Sorted by IPv4 first, as these are more likely to succeed as most RCON implementations only bind to IPv4.
 	addrs.sort_by_key(|a| match a {
 		V4(_) => 0,
@@ -313,10 +1988,14 @@ async fn try_connect(address: impl ToSocketAddrs, timeout_duration: Duration) ->
 	// Attempt connecting to all possible outcomes of the resolve
 	let mut error = None;
 	for addr in addrs {
-		match timeout(timeout_duration, TcpStream::connect(&addr)).await {
+		match timeout(settings.connect_timeout, TcpStream::connect(&addr)).await {
 			Ok(Ok(stream)) => return Ok(stream),  // Successful connection
 			Ok(Err(e)) => error = Some(e.into()), // Connecting failed, store error for later
-			Err(_) => continue,                   // Timeout expired
+			Err(_) if error.is_none() => {
+				// Timeout expired
+				error = Some(IO(std::io::Error::new(ErrorKind::TimedOut, "connect timed out")))
+			}
+			Err(_) => continue,
 		}
 	}
 
@@ -1,10 +1,16 @@
 use std::{
 	fmt::{Debug, Display, Formatter},
 	string::FromUtf8Error,
+	time::Duration,
 	{io, net},
 };
 
-use crate::error::RconError::{AddressParse, UTFEncoding, IO};
+use crate::error::RconError::{
+	AddressParse, BusyReconnecting, Cancelled, CircuitOpen, CommandContainsNewline, CommandTooLong, ConnectionClosing,
+	GaveUp, InMaintenance, InternalError, InvalidId, InvalidSettings, InvalidTemplate, Json, MemoryLimitExceeded,
+	PartialWrite, PasswordIncorrect, Superseded, Throttled, Timeout, UTFEncoding, UnexpectedPacket,
+	UnsolicitedOverflow, Unsupported, VerificationFailed, IO,
+};
 
 /// A common error enum that is returned by all public functions describing different forms of failures that can occur within this library.
 #[derive(Debug)]
@@ -13,16 +19,160 @@ pub enum RconError {
 	AddressParse(net::AddrParseError),
 	/// There was a network issue during connection or exec
 	IO(io::Error),
-	/// The command provided is longer than 1014 characters.
+	/// The command provided is longer than [`MAX_BODY_SIZE`](packet/constant.MAX_BODY_SIZE.html) (1014) bytes.
 	CommandTooLong,
+	/// The command contains a newline while [`NewlineMode::Reject`](enum.NewlineMode.html#variant.Reject) is configured.
+	CommandContainsNewline,
+	/// A previous exec was cancelled by dropping its future while it was writing a packet, possibly leaving half a
+	/// packet on the stream. The connection can't be used anymore, and [`ReConnection`](struct.ReConnection.html)
+	/// reconnects when encountering this. Timeouts and
+	/// [`exec_cancellable`](struct.Connection.html#method.exec_cancellable) never cause this.
+	PartialWrite,
 	/// The server did not respond with proper UTF-8
 	UTFEncoding(FromUtf8Error),
-	/// The server sent a packet with a type we were not expecting.
-	UnexpectedPacket,
-	/// The pass field is incorrect
-	PasswordIncorrect,
+	/// The server sent a packet with a type we were not expecting, containing the body of that packet.
+	UnexpectedPacket(String),
+	/// The pass field is incorrect, containing any message the server sent along with the rejection (such as a
+	/// reason for it), or an empty string if there was none. This is also returned when the server closes the
+	/// connection in response to the auth packet, which is how some servers reject a password.
+	PasswordIncorrect(String),
 	/// Returned by [`ReConnection::exec`](struct.ReConnection.html#method.exec) when [`ReConnection`](struct.ReConnection.html) is busy reconnecting.
 	BusyReconnecting(String),
+	/// Returned by [`ReConnection::exec`](struct.ReConnection.html#method.exec) while its circuit breaker is open, with
+	/// the remaining cool-down before the next reconnect attempt.
+	CircuitOpen(Duration),
+	/// Returned by [`ReConnection::exec`](struct.ReConnection.html#method.exec) during a
+	/// [maintenance window](struct.ReConnection.html#method.schedule_maintenance), with the time remaining until it
+	/// ends.
+	InMaintenance(Duration),
+	/// The server closed the connection while no command was in flight, which with
+	/// [`single_session`](struct.Settings.html#structfield.single_session) means another RCON client took over the
+	/// session.
+	Superseded,
+	/// Returned by [`ReConnection::exec`](struct.ReConnection.html#method.exec) once its
+	/// [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy) gave up on reconnecting, containing
+	/// the error of the last attempt.
+	GaveUp(String),
+	/// The response to the [`verification`](struct.Settings.html#structfield.verification) command did not contain
+	/// the expected text, containing the actual response.
+	VerificationFailed(String),
+	/// The [`unsolicited`](struct.Settings.html#structfield.unsolicited) message buffer overflowed while configured
+	/// with [`OverflowPolicy::Error`](enum.OverflowPolicy.html#variant.Error), containing the amount of messages that
+	/// were dropped.
+	UnsolicitedOverflow(usize),
+	/// The response did not fit within the [`memory_limit`](struct.Settings.html#structfield.memory_limit), containing
+	/// the amount of bytes that were available for it. The response was discarded.
+	MemoryLimitExceeded(usize),
+	/// The full response did not arrive within the time limit of
+	/// [`exec_with_timeout`](struct.Connection.html#method.exec_with_timeout) or the
+	/// [`response_timeout`](struct.Settings.html#structfield.response_timeout), containing that limit.
+	Timeout(Duration),
+	/// The exec was cancelled through [`exec_cancellable`](struct.Connection.html#method.exec_cancellable).
+	Cancelled,
+	/// The server kept responding with a throttle message, as recognized by the
+	/// [`throttle_detector`](struct.Settings.html#structfield.throttle_detector), after all
+	/// [`throttle_retries`](struct.Settings.html#structfield.throttle_retries) were used up.
+	Throttled {
+		/// How long the server asked to wait before sending another command.
+		retry_after: Duration,
+	},
+	/// A [`CommandTemplate`](struct.CommandTemplate.html) is malformed, or could not be filled in with the values that
+	/// were given, containing a description of the problem.
+	InvalidTemplate(String),
+	/// [`Settings`](struct.Settings.html) failed [`validate`](struct.Settings.html#method.validate), containing a
+	/// description of the problem.
+	InvalidSettings(String),
+	/// A [`SteamId`](struct.SteamId.html) or [`EosId`](struct.EosId.html) could not be parsed, or a player name is not
+	/// one the game allows, containing the text that was given.
+	InvalidId(String),
+	/// The game the connection is for has no command for what was asked, such as
+	/// [`ban`](struct.Connection.html#method.ban)ning a player by name on a game that only bans by ID, containing a
+	/// description of the problem.
+	Unsupported(String),
+	/// A background task of the connection panicked, which is a bug in this crate or in a callback such as the
+	/// [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy), containing the panic message. Like
+	/// after an [`IO error`](enum.Error.html#variant.IO), the connection can't be used anymore, and
+	/// [`ReConnection`](struct.ReConnection.html) reconnects when encountering this.
+	InternalError(String),
+	/// The connection was shut down through [`shutdown_signal`](struct.Connection.html#method.shutdown_signal),
+	/// [`close`](struct.Connection.html#method.close) or [`into_stream`](struct.Connection.html#method.into_stream)
+	/// while the exec was in flight, or before it was sent. Unlike an [`IO error`](enum.Error.html#variant.IO), this
+	/// doesn't mean anything went wrong, so [`ReConnection`](struct.ReConnection.html) doesn't reconnect.
+	ConnectionClosing,
+	/// The response to [`exec_json`](struct.Connection.html#method.exec_json) could not be deserialized, containing
+	/// a description of the problem.
+	Json(String),
+}
+
+impl RconError {
+	/// A stable, machine-readable identifier of the error variant, suitable for remote clients to match on.
+	pub fn code(&self) -> &'static str {
+		match self {
+			AddressParse(_) => "address_parse",
+			IO(_) => "io",
+			CommandTooLong => "command_too_long",
+			CommandContainsNewline => "command_contains_newline",
+			PartialWrite => "partial_write",
+			UTFEncoding(_) => "utf_encoding",
+			UnexpectedPacket(_) => "unexpected_packet",
+			PasswordIncorrect(_) => "password_incorrect",
+			BusyReconnecting(_) => "busy_reconnecting",
+			CircuitOpen(_) => "circuit_open",
+			InMaintenance(_) => "in_maintenance",
+			Superseded => "superseded",
+			GaveUp(_) => "gave_up",
+			VerificationFailed(_) => "verification_failed",
+			UnsolicitedOverflow(_) => "unsolicited_overflow",
+			MemoryLimitExceeded(_) => "memory_limit_exceeded",
+			Timeout(_) => "timeout",
+			Cancelled => "cancelled",
+			Throttled { .. } => "throttled",
+			InvalidTemplate(_) => "invalid_template",
+			InvalidSettings(_) => "invalid_settings",
+			InvalidId(_) => "invalid_id",
+			Unsupported(_) => "unsupported",
+			InternalError(_) => "internal_error",
+			ConnectionClosing => "connection_closing",
+			Json(_) => "json",
+		}
+	}
+
+	/// Converts the error into a structured representation that can be returned to remote clients, for example by
+	/// RPC bridges or web APIs. With the `serde` feature enabled, it can be (de)serialized.
+	pub fn to_wire(&self) -> WireError {
+		let mut sources = Vec::new();
+		let mut source = ::std::error::Error::source(self);
+		while let Some(e) = source {
+			sources.push(e.to_string());
+			source = e.source();
+		}
+
+		WireError {
+			code: self.code().to_string(),
+			message: self.to_string(),
+			sources,
+		}
+	}
+}
+
+/// Structured representation of an [`Error`](enum.Error.html), created by
+/// [`Error::to_wire`](enum.Error.html#method.to_wire).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WireError {
+	/// The stable identifier of the error, see [`Error::code`](enum.Error.html#method.code).
+	pub code: String,
+	/// A human-readable description of the error.
+	pub message: String,
+	/// The descriptions of the chain of underlying errors, starting with the direct source.
+	pub sources: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for RconError {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.to_wire().serialize(serializer)
+	}
 }
 
 impl ::std::error::Error for RconError {
@@ -54,6 +204,13 @@ impl From<net::AddrParseError> for RconError {
 	}
 }
 
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for RconError {
+	fn from(e: serde_json::Error) -> Self {
+		Json(e.to_string())
+	}
+}
+
 impl From<FromUtf8Error> for RconError {
 	fn from(e: FromUtf8Error) -> Self {
 		UTFEncoding(e)
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+/// The reply Ark sends for commands that do not produce any output. Ark: Survival Evolved sends it as
+/// `Server received, But no response!!`, while Ark: Survival Ascended varies its casing and punctuation, so it is
+/// matched regardless of those.
+const ARK_NO_RESPONSE: &str = "server received, but no response";
+
+/// A single step of the response post-processing pipeline configured through
+/// [`Settings::response_filters`](struct.Settings.html#structfield.response_filters).
+///
+/// Filters are applied in order to the combined reply before [`exec`](struct.Connection.html#method.exec) returns.
+#[derive(Clone)]
+pub struct ResponseFilter(Arc<dyn Fn(String) -> String + Send + Sync>);
+
+impl ResponseFilter {
+	/// Creates a custom filter from a function transforming the response.
+	pub fn new(filter: impl Fn(String) -> String + Send + Sync + 'static) -> Self {
+		ResponseFilter(Arc::new(filter))
+	}
+
+	/// Removes trailing whitespace and null characters from the response.
+	pub fn trim_end() -> Self {
+		Self::new(|response| {
+			let len = response
+				.trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+				.len();
+			truncated(response, len)
+		})
+	}
+
+	/// Collapses repeated trailing newlines into a single one.
+	pub fn collapse_trailing_newlines() -> Self {
+		Self::new(|response| {
+			let len = response.trim_end_matches(['\n', '\r']).len();
+			if len == response.len() {
+				return response;
+			}
+			let mut response = truncated(response, len);
+			response.push('\n');
+			response
+		})
+	}
+
+	/// Removes Minecraft's `§`-prefixed color and formatting codes.
+	pub fn strip_minecraft_formatting() -> Self {
+		Self::new(|response| {
+			if !response.contains('§') {
+				return response;
+			}
+			let mut result = String::with_capacity(response.len());
+			let mut chars = response.chars();
+			while let Some(c) = chars.next() {
+				match c {
+					'§' => {
+						chars.next();
+					}
+					c => result.push(c),
+				}
+			}
+			result
+		})
+	}
+
+	/// Removes ANSI CSI escape sequences (such as color codes) emitted by some modded or proxied consoles.
+	pub fn strip_ansi() -> Self {
+		Self::new(|response| {
+			if !response.contains(['\x1b', '\u{9b}']) {
+				return response;
+			}
+			let mut result = String::with_capacity(response.len());
+			let mut chars = response.chars().peekable();
+			while let Some(c) = chars.next() {
+				match c {
+					'\x1b' if chars.peek() == Some(&'[') => {
+						chars.next();
+					}
+					'\u{9b}' => (),
+					c => {
+						result.push(c);
+						continue;
+					}
+				}
+				// Skip parameter and intermediate bytes, up to and including the final byte.
+				for c in chars.by_ref() {
+					if ('\x40'..='\x7e').contains(&c) {
+						break;
+					}
+				}
+			}
+			result
+		})
+	}
+
+	/// Replaces the placeholder Ark: Survival Evolved and Ark: Survival Ascended send for commands without output
+	/// with an empty response.
+	pub fn strip_ark_no_response() -> Self {
+		Self::new(|response| {
+			let placeholder = response.trim().trim_end_matches('!').trim_end();
+			match placeholder.eq_ignore_ascii_case(ARK_NO_RESPONSE) {
+				true => String::new(),
+				false => response,
+			}
+		})
+	}
+
+	/// Normalizes the response to Unicode Normalization Form C, so text such as player names that arrives in mixed
+	/// forms compares equal. Requires the `unicode` feature.
+	#[cfg(feature = "unicode")]
+	pub fn nfc() -> Self {
+		Self::new(|response| unicode_normalization::UnicodeNormalization::nfc(response.as_str()).collect())
+	}
+
+	/// Normalizes the response to Unicode Normalization Form KC, which like [`nfc`](#method.nfc) unifies mixed forms,
+	/// but also replaces compatibility characters such as ligatures and full-width letters with their plain
+	/// equivalents. Requires the `unicode` feature.
+	#[cfg(feature = "unicode")]
+	pub fn nfkc() -> Self {
+		Self::new(|response| unicode_normalization::UnicodeNormalization::nfkc(response.as_str()).collect())
+	}
+
+	pub(crate) fn apply(&self, response: String) -> String {
+		(self.0)(response)
+	}
+}
+
+fn truncated(mut s: String, len: usize) -> String {
+	s.truncate(len);
+	s
+}
@@ -0,0 +1,60 @@
+use std::io::ErrorKind;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+	connection::SingleConnection,
+	error::RconError::{self, IO},
+};
+
+type Request = (Vec<u8>, oneshot::Sender<Result<String, RconError>>);
+
+/// A cloneable handle to a [`Connection`](struct.Connection.html), created through
+/// [`Connection::into_handle`](struct.Connection.html#method.into_handle), which allows multiple tasks to exec
+/// concurrently without wrapping the connection in a `Mutex`.
+///
+/// The connection itself is owned by a background task, which runs the commands it receives from all handles one by
+/// one, in the order they arrive. Once every handle has been dropped, the connection is closed.
+#[derive(Clone)]
+pub struct ConnectionHandle {
+	sender: mpsc::Sender<Request>,
+}
+
+impl SingleConnection {
+	/// Moves the connection into a background task, returning a [`ConnectionHandle`](struct.ConnectionHandle.html)
+	/// that can be cloned and shared between tasks.
+	pub fn into_handle(self) -> ConnectionHandle {
+		let (sender, receiver) = mpsc::channel(32);
+		tokio::spawn(drive(self, receiver));
+		ConnectionHandle { sender }
+	}
+}
+
+impl ConnectionHandle {
+	/// This function behaves identical to [`Connection::exec`](struct.Connection.html#method.exec), waiting for the
+	/// commands sent through other handles before it to finish first.
+	pub async fn exec(&self, cmd: impl ToString) -> Result<String, RconError> {
+		self.exec_raw_bytes(cmd.to_string().as_bytes()).await
+	}
+
+	/// This function behaves identical to [`Connection::exec_raw_bytes`](struct.Connection.html#method.exec_raw_bytes),
+	/// with the same ordering as [`exec`](#method.exec).
+	pub async fn exec_raw_bytes(&self, cmd: &[u8]) -> Result<String, RconError> {
+		let (reply, response) = oneshot::channel();
+		self.sender.send((cmd.to_vec(), reply)).await.map_err(|_| stopped())?;
+		response.await.map_err(|_| stopped())?
+	}
+}
+
+/// Runs the commands of all handles on the connection, until every handle is dropped.
+async fn drive(connection: SingleConnection, mut receiver: mpsc::Receiver<Request>) {
+	while let Some((cmd, reply)) = receiver.recv().await {
+		// The caller may have given up waiting, in which case the response is simply discarded.
+		let _ = reply.send(connection.exec_raw_bytes(&cmd).await);
+	}
+	connection.close().await;
+}
+
+fn stopped() -> RconError {
+	IO(std::io::Error::new(ErrorKind::BrokenPipe, "connection task stopped"))
+}
@@ -0,0 +1,36 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::error::RconError;
+
+type Recorder = dyn Fn(&str, Duration, Result<(), &RconError>) + Send + Sync;
+
+/// Observes every exec with its duration and outcome, configured through
+/// [`Settings::exec_hook`](struct.Settings.html#structfield.exec_hook), for example to track latency and error rate
+/// objectives per kind of command without a full metrics stack.
+///
+/// Commands are first mapped to a category (such as "chat", "save" or "query") by a user-supplied classifier, so the
+/// recording side doesn't have to deal with the arguments of every command.
+#[derive(Clone)]
+pub struct ExecHook {
+	classify: Arc<dyn Fn(&str) -> String + Send + Sync>,
+	record: Arc<Recorder>,
+}
+
+impl ExecHook {
+	/// Creates a hook out of `classify`, which maps a command to its category, and `record`, which is called with the
+	/// category, duration and outcome of every exec.
+	pub fn new(
+		classify: impl Fn(&str) -> String + Send + Sync + 'static,
+		record: impl Fn(&str, Duration, Result<(), &RconError>) + Send + Sync + 'static,
+	) -> Self {
+		ExecHook {
+			classify: Arc::new(classify),
+			record: Arc::new(record),
+		}
+	}
+
+	pub(crate) fn observe(&self, cmd: &[u8], duration: Duration, result: &Result<String, RconError>) {
+		let category = (self.classify)(&String::from_utf8_lossy(cmd));
+		(self.record)(&category, duration, result.as_ref().map(|_| ()));
+	}
+}
@@ -0,0 +1,160 @@
+use std::{
+	convert::Infallible,
+	fmt::{Display, Formatter},
+	str::FromStr,
+};
+
+use crate::error::RconError::{self, InvalidId};
+
+// The upper 32 bits of the SteamID64 of every individual account in the public universe
+const INDIVIDUAL: u64 = 0x0110_0001 << 32;
+
+/// The Steam ID of a player, which can be parsed from any of its common notations: a SteamID64
+/// (`76561197960287930`), a Steam2 ID (`STEAM_1:0:11101`) or a Steam3 ID (`[U:1:22202]`). It is displayed as a
+/// SteamID64, which is what most games expect in commands, such as through the [`command!`](macro.command.html)
+/// macro.
+///
+/// Only individual accounts in the public universe are accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SteamId(u64);
+
+impl SteamId {
+	/// Creates a Steam ID from a SteamID64, failing with [`InvalidId`](enum.Error.html#variant.InvalidId) if it
+	/// doesn't belong to an individual account.
+	pub fn from_u64(id: u64) -> Result<Self, RconError> {
+		match id & !0xffff_ffff {
+			INDIVIDUAL => Ok(SteamId(id)),
+			_ => Err(InvalidId(format!("not the SteamID64 of an individual account: {}", id))),
+		}
+	}
+
+	/// Creates a Steam ID from the account ID, the last number of a Steam3 ID.
+	pub fn from_account_id(account_id: u32) -> Self {
+		SteamId(INDIVIDUAL | u64::from(account_id))
+	}
+
+	/// The SteamID64, such as `76561197960287930`.
+	pub fn as_u64(self) -> u64 {
+		self.0
+	}
+
+	/// The account ID, such as `22202`.
+	pub fn account_id(self) -> u32 {
+		self.0 as u32
+	}
+
+	/// The Steam2 notation, such as `STEAM_1:0:11101`. Older games use `STEAM_0` for the same account instead.
+	pub fn steam2(self) -> String {
+		format!("STEAM_1:{}:{}", self.account_id() & 1, self.account_id() >> 1)
+	}
+
+	/// The Steam3 notation, such as `[U:1:22202]`.
+	pub fn steam3(self) -> String {
+		format!("[U:1:{}]", self.account_id())
+	}
+}
+
+impl FromStr for SteamId {
+	type Err = RconError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		parse_steam_id(s).ok_or_else(|| InvalidId(format!("not a Steam ID: {}", s)))
+	}
+}
+
+fn parse_steam_id(s: &str) -> Option<SteamId> {
+	if let Some(steam2) = s.strip_prefix("STEAM_") {
+		let parts: Vec<&str> = steam2.split(':').collect();
+		return match parts[..] {
+			["0" | "1", y @ ("0" | "1"), z] => {
+				let z: u32 = digits(z)?;
+				Some(SteamId::from_account_id(z.checked_mul(2)? | u32::from(y == "1")))
+			}
+			_ => None,
+		};
+	}
+	if let Some(steam3) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+		return Some(SteamId::from_account_id(digits(steam3.strip_prefix("U:1:")?)?));
+	}
+	SteamId::from_u64(digits(s)?).ok()
+}
+
+/// Parses a number consisting of digits only, unlike `parse` which also accepts a leading `+`.
+fn digits<T: FromStr>(s: &str) -> Option<T> {
+	match s.bytes().all(|b| b.is_ascii_digit()) {
+		true => s.parse().ok(),
+		false => None,
+	}
+}
+
+impl Display for SteamId {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// The Epic Online Services ID of a player, as used by Ark: Survival Ascended: 32 hexadecimal digits, such as
+/// `0002b3a5e5f64ed8b8a4c3d2e1f00000`. Parsing accepts uppercase digits, but the ID is always displayed in lowercase.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EosId(String);
+
+impl EosId {
+	/// The ID, in lowercase.
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for EosId {
+	type Err = RconError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.len() == 32 && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+			true => Ok(EosId(s.to_ascii_lowercase())),
+			false => Err(InvalidId(format!("not an EOS ID: {}", s))),
+		}
+	}
+}
+
+impl Display for EosId {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// Refers to a player by whichever identifier is at hand, as game commands usually accept a name as well as an ID.
+/// Parsing never fails: text that is a valid [`SteamId`](struct.SteamId.html) or [`EosId`](struct.EosId.html) becomes
+/// one, anything else is taken as a name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PlayerRef {
+	/// A Steam account.
+	Steam(SteamId),
+	/// An Epic Online Services account.
+	Eos(EosId),
+	/// The display name of a player.
+	Name(String),
+}
+
+impl FromStr for PlayerRef {
+	type Err = Infallible;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Ok(id) = s.parse() {
+			return Ok(PlayerRef::Steam(id));
+		}
+		if let Ok(id) = s.parse() {
+			return Ok(PlayerRef::Eos(id));
+		}
+		Ok(PlayerRef::Name(s.to_string()))
+	}
+}
+
+impl Display for PlayerRef {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PlayerRef::Steam(id) => id.fmt(f),
+			PlayerRef::Eos(id) => id.fmt(f),
+			PlayerRef::Name(name) => f.write_str(name),
+		}
+	}
+}
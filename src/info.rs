@@ -0,0 +1,100 @@
+use crate::{
+	connection::{Profile, SingleConnection},
+	error::RconError,
+	locale::Localization,
+	parse::{count_of, key_values, numbered_lines},
+};
+
+/// General information about a server, normalized across games, as returned by
+/// [`Connection::server_info`](struct.Connection.html#method.server_info).
+///
+/// Not every game reports everything, so any field may be missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerInfo {
+	/// The name of the server.
+	pub name: Option<String>,
+	/// The version of the game the server is running.
+	pub version: Option<String>,
+	/// The map that is currently loaded.
+	pub map: Option<String>,
+	/// The amount of players online.
+	pub players: Option<usize>,
+	/// The maximum amount of players.
+	pub max_players: Option<usize>,
+}
+
+impl SingleConnection {
+	/// Queries general information about the server, using the command that suits the
+	/// [`profile`](struct.Settings.html#structfield.profile) of the connection: `status` for Source engine games (and
+	/// servers without a profile), `list` for Minecraft and `ListPlayers` for Ark. Localized keys in the output of
+	/// `status` are recognized through the [`localization`](struct.Settings.html#structfield.localization).
+	pub async fn server_info(&self) -> Result<ServerInfo, RconError> {
+		let profile = self.settings().profile;
+		let response = self.exec(ServerInfo::command(profile)).await?;
+		Ok(ServerInfo::parse(profile, &response, &self.settings().localization))
+	}
+}
+
+impl ServerInfo {
+	/// The command to query the server info with.
+	pub(crate) fn command(profile: Profile) -> &'static str {
+		match profile {
+			Profile::Generic | Profile::Source => "status",
+			Profile::Minecraft => "list",
+			Profile::Ark => "ListPlayers",
+		}
+	}
+
+	/// Parses the response to the command returned by `command`.
+	pub(crate) fn parse(profile: Profile, response: &str, localization: &Localization) -> Self {
+		match profile {
+			Profile::Generic | Profile::Source => parse_status(response, localization),
+			Profile::Minecraft => parse_minecraft_list(response),
+			Profile::Ark => parse_ark_list_players(response),
+		}
+	}
+}
+
+/// Parses the output of the Source engine `status` command, which consists of `key : value` lines such as
+/// `hostname: My Server` and `players : 2 humans, 0 bots (20/0 max) (not hibernating)`.
+fn parse_status(response: &str, localization: &Localization) -> ServerInfo {
+	let mut info = ServerInfo::default();
+	for (key, value) in key_values(response, ':') {
+		match localization.canonical_key(key) {
+			"hostname" => info.name = Some(value.to_string()),
+			"version" => info.version = value.split_whitespace().next().map(str::to_string),
+			"map" => info.map = value.split_whitespace().next().map(str::to_string),
+			"players" => {
+				info.players = value.split_whitespace().next().and_then(|players| players.parse().ok());
+				info.max_players = value
+					.split_once('(')
+					.and_then(|(_, max)| max.split(['/', ' ']).next())
+					.and_then(|max| max.parse().ok());
+			}
+			_ => (),
+		}
+	}
+	info
+}
+
+/// Parses the output of the Minecraft `list` command: `There are 2 of a max of 20 players online: a, b`.
+fn parse_minecraft_list(response: &str) -> ServerInfo {
+	let (players, max_players) = match count_of(response.split(':').next().unwrap_or_default()) {
+		Some((players, max_players)) => (Some(players), Some(max_players)),
+		None => (None, None),
+	};
+	ServerInfo {
+		players,
+		max_players,
+		..ServerInfo::default()
+	}
+}
+
+/// Counts the players in the output of the Ark `ListPlayers` command, which lists one `0. Name, ID` line per player.
+fn parse_ark_list_players(response: &str) -> ServerInfo {
+	let players = numbered_lines(response).count();
+	ServerInfo {
+		players: Some(players),
+		..ServerInfo::default()
+	}
+}
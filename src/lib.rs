@@ -3,28 +3,138 @@
 //!
 //! There are two primary types to be used with this create,
 //! [`Connection`](struct.Connection.html) and [`ReConnection`](struct.ReConnection.html),
-//! both of these types share the same API,
+//! both of these types share the same API, captured by the [`RconConnection`](trait.RconConnection.html) trait,
 //! the primary difference is that [`ReConnection::exec`](struct.ReConnection.html#method.exec) will never return [`IO errors`](enum.Error.html#variant.IO),
 //! as it will start a new thread to reconnect,
 //! instead, it will return error [`BusyReconnecting`](enum.Error.html#variant.BusyReconnecting),
 //! with a string being a `to_string` representation of the error that caused the reconnect in the first place.
 //!
 //! All public methods use a template to accept all forms of strings that implement `Into<String>`, however the library will always return `std::string::String`
+//!
+//! All timeouts, delays and backoffs are driven by `tokio::time`, so tests can use `tokio::time::pause` (or
+//! `#[tokio::test(start_paused = true)]`) to verify reconnect schedules deterministically instead of sleeping.
+//!
+//! The crate is split into features, so users only pay for what they need:
+//! `client` ([`Connection`](struct.Connection.html)), `reconnection` ([`ReConnection`](struct.ReConnection.html)),
+//! and the game-specific helpers `ark`, `minecraft` and `source`. All of them are enabled by default.
+//! The optional `serde` feature makes errors serializable through [`Error::to_wire`](enum.Error.html#method.to_wire),
+//! and the optional `json` feature adds [`exec_json`](struct.Connection.html#method.exec_json).
+//! The optional `tracing` feature emits a span for every exec, carrying the connection
+//! [`label`](struct.Settings.html#structfield.label) and the packet ids, so application logs can be correlated with
+//! wire captures and server logs. The optional `unicode` feature adds the normalizing
+//! [`ResponseFilter::nfc`](struct.ResponseFilter.html#method.nfc) and
+//! [`ResponseFilter::nfkc`](struct.ResponseFilter.html#method.nfkc), and the optional `stream` feature adds
+//! [`exec_stream`](struct.Connection.html#method.exec_stream). The optional `broker` feature lets a
+//! [`ConnectionHandle`](struct.ConnectionHandle.html#method.serve) share its connection with other processes.
 
 #![deny(warnings, bad_style, missing_docs)]
 
-pub use crate::connection::Settings;
+#[cfg(feature = "client")]
+pub use crate::api::{BoxFuture, RconConnection};
+#[cfg(feature = "client")]
+pub use crate::builder::SettingsBuilder;
+#[cfg(feature = "client")]
+pub use crate::capabilities::{capabilities, Capabilities};
+#[cfg(feature = "client")]
 pub use crate::connection::SingleConnection as Connection;
-pub use crate::error::RconError as Error;
+#[cfg(feature = "client")]
+pub use crate::connection::{
+	AddressFilter, BatchPolicy, CircuitBreaker, CutoffResponse, ExecDetails, FailureKind, NewlineMode, OverflowPolicy,
+	Profile, ReconnectDecision, ReconnectPolicy, Settings, Stats, ThrottleDetector, UnsolicitedBuffer,
+};
+pub use crate::error::{RconError as Error, WireError};
+#[cfg(feature = "client")]
+pub use crate::filter::ResponseFilter;
+#[cfg(feature = "client")]
+pub use crate::handle::ConnectionHandle;
+#[cfg(feature = "client")]
+pub use crate::hook::ExecHook;
+pub use crate::id::{EosId, PlayerRef, SteamId};
+#[cfg(feature = "client")]
+pub use crate::info::ServerInfo;
+#[cfg(feature = "client")]
+pub use crate::locale::Localization;
+#[cfg(feature = "client")]
+pub use crate::moderation::ModerationOutcome;
+#[cfg(feature = "client")]
+pub use crate::poller::{DiffPoller, LineChanges};
+#[cfg(feature = "client")]
+pub use crate::probe::{check_auth, probe, ProbeReport};
+#[cfg(feature = "minecraft")]
+pub use crate::properties::ServerProperties;
+#[cfg(feature = "client")]
+pub use crate::quirks::Quirks;
 #[cfg(feature = "reconnection")]
-pub use crate::reconnect::ReconnectingConnection as ReConnection;
+pub use crate::reconnect::{ConnectionStatus, Event, ReconnectEpisode, ReconnectingConnection as ReConnection};
+#[cfg(feature = "client")]
+pub use crate::selftest::{SelfTestCheck, SelfTestReport};
+#[cfg(feature = "client")]
+pub use crate::split::{CommandSender, Incoming, Response, ResponseReceiver};
+#[cfg(feature = "stream")]
+pub use crate::stream::ResponseStream;
+pub use crate::template::CommandTemplate;
+#[cfg(feature = "client")]
+pub use crate::tracker::{PlayerEvent, PlayerEventKind, PlayerTracker};
+#[cfg(feature = "client")]
+pub use crate::transcript::{Transcript, TranscriptEntry};
 
+#[cfg(feature = "client")]
+mod api;
+#[cfg(feature = "ark")]
+pub mod ark;
+#[cfg(feature = "broker")]
+mod broker;
+#[cfg(feature = "client")]
+mod builder;
+#[cfg(feature = "client")]
+mod capabilities;
+#[cfg(feature = "client")]
 mod connection;
 mod error;
-mod packet;
+#[cfg(feature = "client")]
+mod filter;
+#[cfg(feature = "client")]
+mod handle;
+#[cfg(feature = "client")]
+mod hook;
+mod id;
+#[cfg(feature = "client")]
+mod info;
+#[cfg(feature = "client")]
+mod locale;
+#[cfg(feature = "client")]
+mod moderation;
+// Without the client, only the public packet format helpers are used.
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+pub mod packet;
+#[cfg(feature = "client")]
 mod packet_net;
+pub mod parse;
+#[cfg(feature = "client")]
+mod poller;
+#[cfg(feature = "client")]
+mod probe;
+#[cfg(feature = "minecraft")]
+mod properties;
+#[cfg(feature = "client")]
+mod quirks;
 #[cfg(feature = "reconnection")]
 mod reconnect;
+#[cfg(feature = "client")]
+mod selftest;
+#[cfg(feature = "source")]
+pub mod source;
+#[cfg(feature = "client")]
+mod split;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "client")]
+mod supervise;
+mod template;
+#[cfg(feature = "client")]
+mod tracker;
+#[cfg(feature = "client")]
+mod transcript;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,78 @@
+/// Alternative wordings of what the parsers of this crate look for in responses, for servers that localize them,
+/// set through [`Settings::localization`](struct.Settings.html#structfield.localization).
+///
+/// The default recognizes the English wordings, along with the German, French and Spanish messages Minecraft reports
+/// a missing player with. More can be added without waiting for them to be built in:
+///
+/// ```rust
+/// use rercon::{Localization, Settings};
+///
+/// let settings = Settings {
+///     localization: Localization::default()
+///         .player_not_found("Speler niet gevonden")
+///         .key("Servernaam", "hostname"),
+///     ..Settings::minecraft()
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Localization {
+	// In lowercase
+	not_found: Vec<String>,
+	// Localized key in lowercase, and the key it stands for
+	keys: Vec<(String, String)>,
+}
+
+impl Default for Localization {
+	fn default() -> Self {
+		let not_found = [
+			"not found",
+			"no player",
+			"no user",
+			"no such player",
+			"can't find",
+			"cannot find",
+			"couldn't find",
+			"could not find",
+			"unknown player",
+			"not online",
+			"es wurde kein spieler gefunden",
+			"aucun joueur n'a été trouvé",
+			"no se ha encontrado ningún jugador",
+		];
+		Localization {
+			not_found: not_found.iter().map(|phrase| phrase.to_string()).collect(),
+			keys: Vec::new(),
+		}
+	}
+}
+
+impl Localization {
+	/// Takes responses containing `phrase` (ignoring case) as the server reporting that there is no such player, see
+	/// [`ModerationOutcome::PlayerNotFound`](enum.ModerationOutcome.html#variant.PlayerNotFound).
+	pub fn player_not_found(mut self, phrase: impl ToString) -> Self {
+		self.not_found.push(phrase.to_string().to_lowercase());
+		self
+	}
+
+	/// Reads `key` (ignoring case) in the `key: value` lines parsed by
+	/// [`server_info`](struct.Connection.html#method.server_info) as the English `canonical` key, such as
+	/// `hostname`, `version`, `map` or `players`.
+	pub fn key(mut self, key: impl ToString, canonical: impl ToString) -> Self {
+		self.keys.push((key.to_string().to_lowercase(), canonical.to_string()));
+		self
+	}
+
+	pub(crate) fn is_player_not_found(&self, response: &str) -> bool {
+		let response = response.to_lowercase();
+		self.not_found.iter().any(|phrase| response.contains(phrase.as_str()))
+	}
+
+	/// The English key a key stands for, or the key itself if it has no alternative.
+	pub(crate) fn canonical_key<'a>(&'a self, key: &'a str) -> &'a str {
+		let lowercase = key.to_lowercase();
+		match self.keys.iter().find(|(localized, _)| *localized == lowercase) {
+			Some((_, canonical)) => canonical,
+			None => key,
+		}
+	}
+}
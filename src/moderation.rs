@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use crate::{
+	connection::{Profile, SingleConnection},
+	error::RconError::{self, CommandContainsNewline, InvalidId, Unsupported},
+	id::PlayerRef,
+	locale::Localization,
+	template::CommandTemplate,
+};
+
+/// The result of [`kick`](struct.Connection.html#method.kick) or [`ban`](struct.Connection.html#method.ban), as
+/// told from the response of the server. Localized responses are recognized through the
+/// [`localization`](struct.Settings.html#structfield.localization).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModerationOutcome {
+	/// The server carried out the action, containing its response.
+	Confirmed(String),
+	/// The server reported that there is no such player, containing its response.
+	PlayerNotFound(String),
+}
+
+impl SingleConnection {
+	/// Kicks a player, using the command that suits the [`profile`](struct.Settings.html#structfield.profile) of
+	/// the connection: `kickid` (or `kick` for names) on Source engine games, `kick` on Minecraft and `KickPlayer`
+	/// on Ark. The reason is shown to the player, except on Ark which doesn't support one.
+	///
+	/// Fails with [`Unsupported`](enum.Error.html#variant.Unsupported) if the game can't identify players the way
+	/// `player` does (Ark only by platform ID, Minecraft only by name), or if the connection has no profile. As
+	/// Minecraft takes names unquoted, names it wouldn't allow fail with
+	/// [`InvalidId`](enum.Error.html#variant.InvalidId) rather than being sent.
+	pub async fn kick(&self, player: &PlayerRef, reason: &str) -> Result<ModerationOutcome, RconError> {
+		let cmd = kick_command(self.settings().profile, player, reason)?;
+		let response = self.exec(cmd).await?;
+		Ok(ModerationOutcome::parse(response, &self.settings().localization))
+	}
+
+	/// Bans a player like [`kick`](#method.kick), for `duration` or permanently if `None`: `banid` on Source engine
+	/// games (by Steam ID only, rounding up to whole minutes), `ban` on Minecraft and `BanPlayer` on Ark. Only
+	/// Minecraft shows the reason to the player, and only Source engine games support bans that expire, other games
+	/// fail with [`Unsupported`](enum.Error.html#variant.Unsupported) if a duration is given.
+	pub async fn ban(
+		&self, player: &PlayerRef, duration: Option<Duration>, reason: &str,
+	) -> Result<ModerationOutcome, RconError> {
+		let cmd = ban_command(self.settings().profile, player, duration, reason)?;
+		let response = self.exec(cmd).await?;
+		Ok(ModerationOutcome::parse(response, &self.settings().localization))
+	}
+}
+
+impl ModerationOutcome {
+	/// Tells from the response whether the player was found, as servers respond with a message either way.
+	pub(crate) fn parse(response: String, localization: &Localization) -> Self {
+		match localization.is_player_not_found(&response) {
+			true => ModerationOutcome::PlayerNotFound(response),
+			false => ModerationOutcome::Confirmed(response),
+		}
+	}
+}
+
+/// The command to kick `player` with.
+pub(crate) fn kick_command(profile: Profile, player: &PlayerRef, reason: &str) -> Result<String, RconError> {
+	if reason.contains(['\n', '\r']) {
+		return Err(CommandContainsNewline);
+	}
+	match (profile, player) {
+		(Profile::Source, PlayerRef::Steam(id)) => with_reason(format!("kickid {}", id.steam2()), reason, true),
+		(Profile::Source, PlayerRef::Name(name)) => {
+			with_reason(format!("kick {}", CommandTemplate::escape(name)?), reason, true)
+		}
+		(Profile::Minecraft, PlayerRef::Name(name)) => {
+			with_reason(format!("kick {}", minecraft_name(name)?), reason, false)
+		}
+		(Profile::Ark, PlayerRef::Steam(_) | PlayerRef::Eos(_)) => Ok(format!("KickPlayer {}", player)),
+		(profile, player) => Err(unsupported("kick", profile, player)),
+	}
+}
+
+/// The command to ban `player` with.
+pub(crate) fn ban_command(
+	profile: Profile, player: &PlayerRef, duration: Option<Duration>, reason: &str,
+) -> Result<String, RconError> {
+	if reason.contains(['\n', '\r']) {
+		return Err(CommandContainsNewline);
+	}
+	match (profile, player, duration) {
+		(Profile::Source, PlayerRef::Steam(id), duration) => {
+			// Zero minutes is permanent, so shorter bans are rounded up.
+			let minutes = match duration {
+				Some(duration) => duration.as_secs().div_ceil(60).max(1),
+				None => 0,
+			};
+			Ok(format!("banid {} {} kick", minutes, id.steam2()))
+		}
+		(Profile::Minecraft, PlayerRef::Name(name), None) => {
+			with_reason(format!("ban {}", minecraft_name(name)?), reason, false)
+		}
+		(Profile::Ark, PlayerRef::Steam(_) | PlayerRef::Eos(_), None) => Ok(format!("BanPlayer {}", player)),
+		(profile, _, Some(_)) if profile == Profile::Minecraft || profile == Profile::Ark => {
+			Err(Unsupported(format!("{:?} can't ban a player temporarily", profile)))
+		}
+		(profile, player, _) => Err(unsupported("ban", profile, player)),
+	}
+}
+
+/// Appends the reason to the command, if any. Games that take the rest of the line as the reason get it unquoted.
+fn with_reason(cmd: String, reason: &str, quoted: bool) -> Result<String, RconError> {
+	Ok(match (reason.is_empty(), quoted) {
+		(true, _) => cmd,
+		(false, true) => format!("{} {}", cmd, CommandTemplate::escape(reason)?),
+		(false, false) => format!("{} {}", cmd, reason),
+	})
+}
+
+/// Checks that `name` is a valid Minecraft name (1 to 16 letters, digits or underscores), as it can't be quoted and
+/// anything else could end it early or run another command.
+fn minecraft_name(name: &str) -> Result<&str, RconError> {
+	let valid = (1..=16).contains(&name.len()) && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+	match valid {
+		true => Ok(name),
+		false => Err(InvalidId(name.to_string())),
+	}
+}
+
+fn unsupported(action: &str, profile: Profile, player: &PlayerRef) -> RconError {
+	let kind = match player {
+		PlayerRef::Steam(_) => "Steam ID",
+		PlayerRef::Eos(_) => "EOS ID",
+		PlayerRef::Name(_) => "name",
+	};
+	Unsupported(format!("{:?} can't {} a player by {}", profile, action, kind))
+}
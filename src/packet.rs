@@ -1,61 +1,127 @@
-use std::io::Write;
+//! Constants and helpers describing the RCON packet format, for validating command lengths or building tooling.
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{self, ErrorKind};
 
-use crate::error::{RconError, RconError::CommandTooLong};
+use crate::error::{
+	RconError,
+	RconError::{CommandTooLong, IO},
+};
 
-type PacketType = i32;
+/// The type of a packet, as sent in its header.
+pub type PacketType = i32;
 
-pub(crate) const TYPE_AUTH: PacketType = 3;
-pub(crate) const TYPE_EXEC: PacketType = 2;
-pub(crate) const TYPE_RESPONSE: PacketType = 0;
-pub(crate) const TYPE_AUTH_RESPONSE: PacketType = 2;
+/// `SERVERDATA_AUTH`, sent by the client to authenticate.
+pub const TYPE_AUTH: PacketType = 3;
+/// `SERVERDATA_EXECCOMMAND`, sent by the client to execute a command.
+pub const TYPE_EXEC: PacketType = 2;
+/// `SERVERDATA_RESPONSE_VALUE`, sent by the server in response to a command.
+pub const TYPE_RESPONSE: PacketType = 0;
+/// `SERVERDATA_AUTH_RESPONSE`, sent by the server in response to authentication. Shares its value with
+/// [`TYPE_EXEC`](constant.TYPE_EXEC.html).
+pub const TYPE_AUTH_RESPONSE: PacketType = 2;
 
-pub(crate) struct Packet {
+/// Size of the length field preceding every packet.
+pub const LENGTH_SIZE: usize = 4;
+/// Size of the id and type fields at the start of every packet.
+pub const HEADER_SIZE: usize = 8;
+/// Size of the two null terminators following the body of every packet.
+pub const TERMINATOR_SIZE: usize = 2;
+/// Amount of bytes counted by the length field on top of the body: the header and terminators.
+pub const PACKET_OVERHEAD: usize = HEADER_SIZE + TERMINATOR_SIZE;
+/// Maximum value of the length field of a packet sent to the server.
+pub const MAX_PACKET_SIZE: usize = 1024;
+/// Maximum length of a command body in bytes.
+pub const MAX_BODY_SIZE: usize = MAX_PACKET_SIZE - PACKET_OVERHEAD;
+/// Maximum value of the length field of a packet that is read. Far above the 4096 byte bodies games send, it only
+/// keeps a corrupt length field from causing a huge allocation.
+pub(crate) const MAX_READ_PACKET_SIZE: usize = 1024 * 1024;
+
+/// A single RCON packet, as returned by [`exec_raw`](../struct.Connection.html#method.exec_raw).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
 	id: i32,
 	packet_type: PacketType,
-	body: String,
+	body: Vec<u8>,
 }
 
 impl Packet {
-	pub(crate) fn new(id: i32, packet_type: PacketType, body: String) -> Self {
-		Packet { id, packet_type, body }
+	pub(crate) fn new(id: i32, packet_type: PacketType, body: impl Into<Vec<u8>>) -> Self {
+		Packet {
+			id,
+			packet_type,
+			body: body.into(),
+		}
 	}
 
-	pub(crate) fn get_id(&self) -> i32 {
+	/// The ID of the packet, which for responses is the ID of the command they belong to.
+	pub fn get_id(&self) -> i32 {
 		self.id
 	}
 
-	pub(crate) fn get_packet_type(&self) -> PacketType {
+	/// The type of the packet, such as [`TYPE_RESPONSE`](constant.TYPE_RESPONSE.html).
+	pub fn get_packet_type(&self) -> PacketType {
 		self.packet_type
 	}
 
-	pub(crate) fn get_body(&self) -> &str {
+	/// The body of the packet, without its null terminators.
+	pub fn get_body(&self) -> &[u8] {
 		&self.body
 	}
 
+	/// Takes the body out of the packet.
+	pub fn into_body(self) -> Vec<u8> {
+		self.body
+	}
+
+	/// The amount of bytes a packet with a body of `body_len` bytes takes up on the wire, including its length field.
+	pub const fn encoded_len(body_len: usize) -> usize {
+		LENGTH_SIZE + PACKET_OVERHEAD + body_len
+	}
+
 	pub(crate) fn decode_packet_buffer(len: usize, buf: &[u8]) -> Result<Self, RconError> {
-		let id = (&buf[0..4]).read_i32::<LittleEndian>()?;
-		let packet_type = (&buf[4..8]).read_i32::<LittleEndian>()?;
-		let body = String::from_utf8((&buf[8..(len - 2)]).to_vec())?;
+		if len < PACKET_OVERHEAD || buf.len() < len {
+			return Err(invalid_length(len));
+		}
+		let id = read_i32(&buf[0..4]);
+		let packet_type = read_i32(&buf[4..8]);
+		let body = buf[HEADER_SIZE..(len - TERMINATOR_SIZE)].to_vec();
 
 		Ok(Packet { id, packet_type, body })
 	}
 
 	pub(crate) fn create_packet_buffer(&self) -> Result<Vec<u8>, RconError> {
-		if self.body.len() > 1014 {
-			// 1024 - 10
+		let mut buf = Vec::with_capacity(Self::encoded_len(self.body.len()));
+		Self::encode(self.id, self.packet_type, &self.body, &mut buf)?;
+		Ok(buf)
+	}
+
+	/// Appends a packet to `buf` without building a `Packet` first, so commands can be written straight from the
+	/// caller's string into a reused buffer.
+	pub(crate) fn encode(id: i32, packet_type: PacketType, body: &[u8], buf: &mut Vec<u8>) -> Result<(), RconError> {
+		if body.len() > MAX_BODY_SIZE {
 			return Err(CommandTooLong);
 		}
 
-		let mut buf = Vec::with_capacity(self.body.len() + 14);
-		buf.write_i32::<LittleEndian>(self.body.len() as i32 + 10)?;
-		buf.write_i32::<LittleEndian>(self.id)?;
-		buf.write_i32::<LittleEndian>(self.packet_type)?;
-		buf.write_all(self.body.as_bytes())?;
-		buf.write_u8(0)?; // null-terminate the string
-		buf.write_u8(0)?; // And again, because RCON
+		buf.reserve(Self::encoded_len(body.len()));
+		buf.extend_from_slice(&((body.len() + PACKET_OVERHEAD) as i32).to_le_bytes());
+		buf.extend_from_slice(&id.to_le_bytes());
+		buf.extend_from_slice(&packet_type.to_le_bytes());
+		buf.extend_from_slice(body);
+		buf.push(0); // null-terminate the string
+		buf.push(0); // And again, because RCON
 
-		Ok(buf)
+		Ok(())
 	}
 }
+
+fn read_i32(buf: &[u8]) -> i32 {
+	i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+/// The error for a length field that can't belong to a valid packet, which leaves the stream impossible to follow.
+pub(crate) fn invalid_length(len: impl std::fmt::Display) -> RconError {
+	IO(io::Error::new(
+		ErrorKind::InvalidData,
+		format!("invalid packet length {}", len),
+	))
+}
@@ -1,13 +1,14 @@
-// These functions were placed in a separate file due to signature conflicts between ByteOrders
-// read_ functions and tokio's async equivalents. But sadly we're required to use ByteOrder as well
-// because Tokio only supports Big Endian.
+// These functions were placed in a separate file to keep the async networking code apart from the
+// (synchronous) packet encoding in packet.rs.
 
 use std::pin::Pin;
 
-use byteorder::{ByteOrder, LittleEndian};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-use crate::{error::RconError, packet::Packet};
+use crate::{
+	error::RconError,
+	packet::{invalid_length, Packet, PACKET_OVERHEAD},
+};
 
 impl Packet {
 	pub(crate) async fn send_internal(&self, mut stream: Pin<&mut impl AsyncWrite>) -> Result<(), RconError> {
@@ -15,13 +16,17 @@ impl Packet {
 		Ok(stream.flush().await?)
 	}
 
-	pub(crate) async fn read(mut stream: Pin<&mut impl AsyncRead>) -> Result<Self, RconError> {
-		let mut len_buf = vec![0; 4];
-		stream.read_exact(&mut len_buf).await?;
-		let len = LittleEndian::read_i32(&len_buf) as usize;
+	/// Reads a packet whose length field is at most `max_len`, as the length is sent by the peer and can't be trusted
+	/// to allocate the packet with.
+	pub(crate) async fn read(mut stream: Pin<&mut impl AsyncRead>, max_len: usize) -> Result<Self, RconError> {
+		let len = stream.read_i32_le().await?;
+		if len < PACKET_OVERHEAD as i32 || len as usize > max_len {
+			return Err(invalid_length(len));
+		}
+		let len = len as usize;
 
-		let mut buf = Vec::with_capacity(len);
-		stream.take(len as u64).read_to_end(&mut buf).await?;
-		Ok(Packet::decode_packet_buffer(len, &buf)?)
+		let mut buf = vec![0; len];
+		stream.read_exact(&mut buf).await?;
+		Packet::decode_packet_buffer(len, &buf)
 	}
 }
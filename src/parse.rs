@@ -0,0 +1,82 @@
+//! Building blocks for parsing the output of RCON commands, which the game-specific parsers of this crate are built
+//! on as well.
+
+use std::collections::HashMap;
+
+/// Splits lines such as `hostname: My Server` on the first `separator`, trimming the key and the value. Lines without
+/// the separator are skipped.
+pub fn key_values(response: &str, separator: char) -> impl Iterator<Item = (&str, &str)> {
+	response
+		.lines()
+		.filter_map(move |line| line.split_once(separator))
+		.map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Splits a comma-separated list such as `Steve, Alex`, trimming the items and skipping empty ones. Newlines are
+/// treated like commas, as long lists are sometimes wrapped.
+pub fn comma_list(list: &str) -> impl Iterator<Item = &str> {
+	list.split([',', '\n']).map(str::trim).filter(|item| !item.is_empty())
+}
+
+/// Parses lines of numbered lists such as `0. Steve`, returning the number and the rest of the line. Lines that are
+/// not numbered are skipped.
+pub fn numbered_lines(response: &str) -> impl Iterator<Item = (usize, &str)> {
+	response.lines().filter_map(|line| {
+		let (number, rest) = line.trim().split_once(". ")?;
+		Some((number.parse().ok()?, rest))
+	})
+}
+
+/// Finds a count and its maximum, being the first two numbers in the text, as in `2 of 20`, `2/20` or
+/// `There are 2 of a max of 20 players online`.
+pub fn count_of(text: &str) -> Option<(usize, usize)> {
+	let mut numbers = text
+		.split(|c: char| !c.is_ascii_digit())
+		.filter_map(|number| number.parse().ok());
+	Some((numbers.next()?, numbers.next()?))
+}
+
+/// Parses a table of which the columns are aligned with spaces, such as
+///
+/// ```text
+/// name    ping  state
+/// Steve   23    active
+/// ```
+///
+/// The first line is the header, and every column starts where a word of the header starts. Returns a map per row,
+/// from the header of a column to the trimmed content of the cell. Empty lines are skipped.
+pub fn fixed_width_table(response: &str) -> Vec<HashMap<&str, &str>> {
+	let mut lines = response.lines().filter(|line| !line.trim().is_empty());
+	let header = match lines.next() {
+		Some(header) => header,
+		None => return Vec::new(),
+	};
+
+	// The columns, by the character position they start at
+	let mut columns = Vec::new();
+	let mut previous = ' ';
+	for (position, (offset, c)) in header.char_indices().enumerate() {
+		if previous == ' ' && c != ' ' {
+			let name = header[offset..].split(' ').next().unwrap_or_default();
+			columns.push((position, name));
+		}
+		previous = c;
+	}
+
+	lines
+		.map(|line| {
+			let offset = |position| {
+				line.char_indices()
+					.nth(position)
+					.map_or(line.len(), |(offset, _)| offset)
+			};
+			let ends = columns.iter().skip(1).map(|(position, _)| offset(*position));
+			let ends = ends.chain(Some(line.len()));
+			columns
+				.iter()
+				.zip(ends)
+				.map(|((position, name), end)| (*name, line[offset(*position).min(end)..end].trim()))
+				.collect()
+		})
+		.collect()
+}
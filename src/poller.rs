@@ -0,0 +1,125 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+use crate::{connection::SingleConnection, error::RconError};
+
+/// The lines that changed between two responses, see [`DiffPoller`](struct.DiffPoller.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineChanges {
+	/// Lines of the new response that were not in the previous one, in the order they appear in.
+	pub added: Vec<String>,
+	/// Lines of the previous response that are not in the new one, in the order they appeared in.
+	pub removed: Vec<String>,
+}
+
+impl LineChanges {
+	/// Whether no line changed.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty()
+	}
+}
+
+/// Runs a command periodically, handing out only the lines that changed since the previous run, a building block for
+/// polling chat, tailing logs or tracking anything else a server only reports through a command's output.
+///
+/// Lines are compared as a whole, ignoring their order, and repeated lines are counted, so a message that is sent
+/// twice is reported twice. Empty lines are skipped. The first run reports every line as added.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use rercon::{Connection, DiffPoller, Settings};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let connection = Connection::open("123.456.789.123:27020", "my_secret_password", Settings::ark()).await.unwrap();
+///     let mut poller = DiffPoller::new(&connection, "GetChat", Duration::from_secs(5));
+///     loop {
+///         for line in poller.next().await.unwrap().added {
+///             println!("{}", line);
+///         }
+///     }
+/// }
+/// ```
+pub struct DiffPoller<'a> {
+	connection: &'a SingleConnection,
+	command: String,
+	interval: Interval,
+	lines: Vec<String>,
+}
+
+impl<'a> DiffPoller<'a> {
+	/// Creates a poller running `command` on `connection` every `interval`, starting right away. A run that takes
+	/// longer than the interval delays the runs after it, rather than having them catch up in a burst.
+	///
+	/// # Panics
+	/// Panics if `interval` is zero.
+	pub fn new(connection: &'a SingleConnection, command: impl ToString, interval: Duration) -> Self {
+		let mut interval = time::interval(interval);
+		interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		DiffPoller {
+			connection,
+			command: command.to_string(),
+			interval,
+			lines: Vec::new(),
+		}
+	}
+
+	/// The command that is polled.
+	pub fn command(&self) -> &str {
+		&self.command
+	}
+
+	/// The lines of the last response.
+	pub fn lines(&self) -> impl Iterator<Item = &str> {
+		self.lines.iter().map(String::as_str)
+	}
+
+	/// Compares a response of the [`command`](#method.command) to the previous one, returning the lines that changed.
+	pub fn update(&mut self, response: &str) -> LineChanges {
+		let lines: Vec<String> = response
+			.lines()
+			.filter(|line| !line.is_empty())
+			.map(str::to_string)
+			.collect();
+		let changes = LineChanges {
+			added: difference(&lines, &self.lines),
+			removed: difference(&self.lines, &lines),
+		};
+		self.lines = lines;
+		changes
+	}
+
+	/// Waits until a run of the command has a response that differs from the previous one, returning the lines that
+	/// changed.
+	pub async fn next(&mut self) -> Result<LineChanges, RconError> {
+		loop {
+			self.interval.tick().await;
+			let response = self.connection.exec(&self.command).await?;
+			let changes = self.update(&response);
+			if !changes.is_empty() {
+				return Ok(changes);
+			}
+		}
+	}
+}
+
+/// The lines in `lines` that are not in `other`, counting repeated lines.
+fn difference(lines: &[String], other: &[String]) -> Vec<String> {
+	let mut counts = HashMap::new();
+	for line in other {
+		*counts.entry(line.as_str()).or_insert(0) += 1;
+	}
+	lines
+		.iter()
+		.filter(|line| match counts.get_mut(line.as_str()) {
+			Some(count) if *count > 0 => {
+				*count -= 1;
+				false
+			}
+			_ => true,
+		})
+		.cloned()
+		.collect()
+}
@@ -0,0 +1,62 @@
+use std::{io::ErrorKind, time::Duration};
+
+use tokio::{
+	net::ToSocketAddrs,
+	time::{timeout, Instant},
+};
+
+use crate::{
+	connection::{Settings, SingleConnection},
+	error::RconError::{self, IO},
+};
+
+/// The outcome of a successful [`probe`](fn.probe.html).
+#[derive(Clone, Debug)]
+pub struct ProbeReport {
+	/// Time taken to connect and authenticate.
+	pub connect_time: Duration,
+	/// Time taken for the server to answer the ping.
+	pub round_trip: Duration,
+}
+
+/// Connects, authenticates and sends a [`ping`](struct.Connection.html#method.ping), which runs the
+/// [`terminator`](struct.Settings.html#structfield.terminator) so servers that ignore empty commands are probed
+/// too, then closes the connection again.
+///
+/// This is meant for readiness checks, where a hanging check is worse than a failed one. The whole probe is bounded
+/// by [`connect_budget`](struct.Settings.html#structfield.connect_budget), or by
+/// [`connect_timeout`](struct.Settings.html#structfield.connect_timeout) if no budget is set, and fails with a
+/// `TimedOut` IO error once that is exceeded. No tasks are left running once this returns.
+pub async fn probe(
+	address: impl ToSocketAddrs, pass: impl ToString, settings: Settings,
+) -> Result<ProbeReport, RconError> {
+	let deadline = settings.connect_budget.unwrap_or(settings.connect_timeout);
+	timeout(deadline, probe_inner(address, pass, settings))
+		.await
+		.map_err(|_| IO(std::io::Error::new(ErrorKind::TimedOut, "probe deadline exceeded")))?
+}
+
+async fn probe_inner(
+	address: impl ToSocketAddrs, pass: impl ToString, settings: Settings,
+) -> Result<ProbeReport, RconError> {
+	let start = Instant::now();
+	let connection = SingleConnection::open(address, pass, settings).await?;
+	let connect_time = start.elapsed();
+
+	let round_trip = connection.ping().await;
+	connection.close().await;
+
+	Ok(ProbeReport {
+		connect_time,
+		round_trip: round_trip?,
+	})
+}
+
+/// Connects and authenticates, then closes the connection right away, for setup wizards that validate RCON
+/// credentials before storing them. Fails with [`PasswordIncorrect`](enum.Error.html#variant.PasswordIncorrect) if
+/// the server rejects the password. Any [`verification`](struct.Settings.html#structfield.verification) configured in
+/// `settings` is run as well, to catch credentials that belong to a different server.
+pub async fn check_auth(address: impl ToSocketAddrs, pass: impl ToString, settings: Settings) -> Result<(), RconError> {
+	SingleConnection::open(address, pass, settings).await?.close().await;
+	Ok(())
+}
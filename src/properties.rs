@@ -0,0 +1,150 @@
+use std::{
+	fs::File,
+	io::{self, BufRead, BufReader, ErrorKind, Read},
+	net::{IpAddr, SocketAddr},
+	path::Path,
+};
+
+use crate::error::RconError;
+
+const DEFAULT_PORT: u16 = 25575;
+
+/// RCON connection parameters read from a Minecraft `server.properties` file, for tools running on the same host as
+/// the server.
+///
+/// # Example
+/// ```rust,no_run
+/// use rercon::{Connection, ServerProperties, Settings};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let properties = ServerProperties::from_path("/srv/minecraft/server.properties").unwrap();
+///     let mut connection = Connection::open(properties.address(), properties.password, Settings::minecraft()).await.unwrap();
+///     println!("{}", connection.exec("list").await.unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerProperties {
+	/// Whether the server has RCON enabled (`enable-rcon`).
+	pub enabled: bool,
+	/// The IP the server binds to (`server-ip`), if any.
+	pub ip: Option<String>,
+	/// The port RCON listens on (`rcon.port`).
+	pub port: u16,
+	/// The RCON password (`rcon.password`).
+	pub password: String,
+}
+
+impl ServerProperties {
+	/// Reads the properties from the `server.properties` file at the given path.
+	pub fn from_path(path: impl AsRef<Path>) -> Result<Self, RconError> {
+		Self::from_reader(File::open(path)?)
+	}
+
+	/// Reads the properties from the contents of a `server.properties` file.
+	pub fn from_reader(reader: impl Read) -> Result<Self, RconError> {
+		let mut properties = ServerProperties {
+			enabled: false,
+			ip: None,
+			port: DEFAULT_PORT,
+			password: String::new(),
+		};
+
+		let mut logical_line = String::new();
+		let mut lines = BufReader::new(reader).lines();
+		while let Some(line) = lines.next().transpose()? {
+			// A line ending in an unescaped backslash continues on the next, without its leading whitespace. Comments
+			// can't be continued.
+			let line = line.trim_start();
+			if logical_line.is_empty() && (line.starts_with('#') || line.starts_with('!')) {
+				continue;
+			}
+			logical_line.push_str(line);
+			let backslashes = logical_line.chars().rev().take_while(|&c| c == '\\').count();
+			if backslashes % 2 == 1 {
+				logical_line.pop();
+				continue;
+			}
+
+			let (key, value) = match parse_line(&std::mem::take(&mut logical_line)) {
+				Some(pair) => pair,
+				None => continue,
+			};
+			match key.as_str() {
+				"enable-rcon" => properties.enabled = value == "true",
+				"server-ip" if !value.is_empty() => properties.ip = Some(value),
+				"rcon.port" => {
+					properties.port = value
+						.parse()
+						.map_err(|_| io::Error::new(ErrorKind::InvalidData, "invalid rcon.port in server.properties"))?
+				}
+				"rcon.password" => properties.password = value,
+				_ => (),
+			}
+		}
+
+		Ok(properties)
+	}
+
+	/// The address to connect to, using the loopback address if the server does not bind to a specific IP.
+	pub fn address(&self) -> String {
+		let ip = self.ip.as_deref().unwrap_or("127.0.0.1");
+		match ip.parse::<IpAddr>() {
+			// Puts IPv6 addresses between brackets
+			Ok(ip) => SocketAddr::new(ip, self.port).to_string(),
+			Err(_) => format!("{}:{}", ip, self.port),
+		}
+	}
+}
+
+/// Parses a single line in the Java properties format into a key/value pair, skipping blank lines and comments.
+fn parse_line(line: &str) -> Option<(String, String)> {
+	let line = line.trim_start();
+	if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+		return None;
+	}
+
+	let mut key = String::new();
+	let mut chars = line.chars();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => key.push(unescape(chars.next()?, &mut chars)),
+			'=' | ':' => break,
+			c if c.is_whitespace() => {
+				// Whitespace may separate key and value, optionally followed by a separator.
+				let rest = chars.as_str().trim_start();
+				chars = rest.strip_prefix(['=', ':']).unwrap_or(rest).chars();
+				break;
+			}
+			c => key.push(c),
+		}
+	}
+
+	let mut value = String::new();
+	let mut chars = chars.as_str().trim_start().chars();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => value.push(unescape(chars.next()?, &mut chars)),
+			c => value.push(c),
+		}
+	}
+
+	Some((key, value))
+}
+
+fn unescape(c: char, chars: &mut std::str::Chars) -> char {
+	match c {
+		'n' => '\n',
+		'r' => '\r',
+		't' => '\t',
+		'f' => '\x0c',
+		'u' => {
+			let code: String = chars.take(4).collect();
+			u32::from_str_radix(&code, 16)
+				.ok()
+				.and_then(char::from_u32)
+				.unwrap_or(char::REPLACEMENT_CHARACTER)
+		}
+		c => c,
+	}
+}
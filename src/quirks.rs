@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use crate::connection::{NewlineMode, Settings};
+
+/// Overrides of the protocol quirk settings, meant to be loaded from a configuration file (with the `serde` feature
+/// enabled) so the behaviour against a nonconformant server can be fixed without changing code.
+///
+/// Every field that is set replaces the corresponding field of [`Settings`](struct.Settings.html) when applied
+/// through [`Settings::with_quirks`](struct.Settings.html#method.with_quirks), the others are left untouched. For
+/// example, in TOML:
+///
+/// ```toml
+/// terminator = "echo"
+/// lossy_utf8 = true
+/// newline_mode = "split"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Quirks {
+	/// See [`Settings::terminator`](struct.Settings.html#structfield.terminator).
+	pub terminator: Option<String>,
+	/// See [`Settings::newline_mode`](struct.Settings.html#structfield.newline_mode).
+	pub newline_mode: Option<NewlineMode>,
+	/// See [`Settings::lossy_utf8`](struct.Settings.html#structfield.lossy_utf8).
+	pub lossy_utf8: Option<bool>,
+	/// See [`Settings::skip_auth_without_password`](struct.Settings.html#structfield.skip_auth_without_password).
+	pub skip_auth_without_password: Option<bool>,
+	/// See [`Settings::source_quirks`](struct.Settings.html#structfield.source_quirks).
+	pub source_quirks: Option<bool>,
+	/// See [`Settings::dedup_responses`](struct.Settings.html#structfield.dedup_responses).
+	pub dedup_responses: Option<bool>,
+	/// See [`Settings::auth_delay`](struct.Settings.html#structfield.auth_delay), in milliseconds. Zero disables it.
+	pub auth_delay_ms: Option<u64>,
+	/// See [`Settings::packet_pacing`](struct.Settings.html#structfield.packet_pacing), in milliseconds. Zero
+	/// disables it.
+	pub packet_pacing_ms: Option<u64>,
+	/// See [`Settings::connect_timeout`](struct.Settings.html#structfield.connect_timeout), in milliseconds.
+	pub connect_timeout_ms: Option<u64>,
+	/// See [`Settings::auth_timeout`](struct.Settings.html#structfield.auth_timeout), in milliseconds.
+	pub auth_timeout_ms: Option<u64>,
+	/// See [`Settings::response_timeout`](struct.Settings.html#structfield.response_timeout), in milliseconds. Zero
+	/// disables it.
+	pub response_timeout_ms: Option<u64>,
+}
+
+impl Settings {
+	/// Applies the overrides that are set in `quirks` to these settings.
+	pub fn with_quirks(mut self, quirks: &Quirks) -> Self {
+		if let Some(terminator) = &quirks.terminator {
+			self.terminator = terminator.clone();
+		}
+		if let Some(newline_mode) = quirks.newline_mode {
+			self.newline_mode = newline_mode;
+		}
+		if let Some(lossy_utf8) = quirks.lossy_utf8 {
+			self.lossy_utf8 = lossy_utf8;
+		}
+		if let Some(skip_auth_without_password) = quirks.skip_auth_without_password {
+			self.skip_auth_without_password = skip_auth_without_password;
+		}
+		if let Some(source_quirks) = quirks.source_quirks {
+			self.source_quirks = source_quirks;
+		}
+		if let Some(dedup_responses) = quirks.dedup_responses {
+			self.dedup_responses = dedup_responses;
+		}
+		if let Some(auth_delay) = quirks.auth_delay_ms {
+			self.auth_delay = Some(Duration::from_millis(auth_delay)).filter(|delay| !delay.is_zero());
+		}
+		if let Some(packet_pacing) = quirks.packet_pacing_ms {
+			self.packet_pacing = Some(Duration::from_millis(packet_pacing)).filter(|pacing| !pacing.is_zero());
+		}
+		if let Some(connect_timeout) = quirks.connect_timeout_ms {
+			self.connect_timeout = Duration::from_millis(connect_timeout);
+		}
+		if let Some(auth_timeout) = quirks.auth_timeout_ms {
+			self.auth_timeout = Duration::from_millis(auth_timeout);
+		}
+		if let Some(response_timeout) = quirks.response_timeout_ms {
+			self.response_timeout = Some(Duration::from_millis(response_timeout)).filter(|timeout| !timeout.is_zero());
+		}
+		self
+	}
+}
@@ -1,27 +1,284 @@
-use std::{mem, ops::DerefMut, panic::panic_any, sync::Arc, time::Duration};
+use std::{
+	collections::{hash_map::RandomState, VecDeque},
+	fmt::{Debug, Formatter},
+	future::Future,
+	hash::{BuildHasher, Hasher},
+	mem,
+	net::SocketAddr,
+	panic::panic_any,
+	sync::Arc,
+	time::Duration,
+};
 
 use tokio::{
+	io::AsyncWrite,
 	select,
-	sync::{Mutex, Notify},
+	sync::{broadcast, Mutex, MutexGuard, Notify},
 	task::JoinHandle,
-	time::sleep,
+	time::{sleep, sleep_until, Instant},
 };
 
+#[cfg(feature = "stream")]
+use crate::stream::ResponseStream;
 use crate::{
-	connection::{Settings, SingleConnection},
-	error::RconError::{self, BusyReconnecting, IO},
-	reconnect::Status::{Connected, Disconnected, Stopped},
+	capabilities::Capabilities,
+	connection::{
+		BatchPolicy, Cutoff, CutoffResponse, ExecDetails, FailureKind, Interrupt, ReconnectDecision, Settings,
+		ShutdownHandle, SingleConnection, Stats, MAX_AUTH_FAILURE_BACKOFF,
+	},
+	error::RconError::{
+		self, BusyReconnecting, CircuitOpen, ConnectionClosing, InMaintenance, InternalError, PartialWrite,
+		PasswordIncorrect, Superseded, IO,
+	},
+	id::PlayerRef,
+	info::ServerInfo,
+	moderation::{ban_command, kick_command, ModerationOutcome},
+	packet::Packet,
+	reconnect::Status::{Connected, Disconnected, GaveUp, Stopped},
+	selftest::SelfTestReport,
+	supervise::catch_unwind,
 };
 
+// How many of the most recent reconnect episodes are kept
+const HISTORY_LEN: usize = 32;
+
+#[derive(Debug)]
 enum Status {
-	Connected(SingleConnection),
+	Connected(Box<SingleConnection>),
 	Disconnected(String),
+	GaveUp(String),
 	Stopped,
 }
 
 struct Internal {
 	status: Mutex<Status>,
+	// Shuts down the connection in the status, which an exec holds on to until it completes
+	shutdown: std::sync::Mutex<Option<ShutdownHandle>>,
 	close_connection: Notify,
+	events: broadcast::Sender<Event>,
+	// Response to the restart probe of the current server instance, if one is configured
+	instance: Mutex<Option<String>>,
+	breaker: Mutex<Breaker>,
+	// Set while disconnected
+	outage: Mutex<Option<Outage>>,
+	// Authenticated spare connection and its restart probe response, if warm_standby is enabled
+	standby: Mutex<Option<(SingleConnection, Option<String>)>>,
+	// Start and end of every maintenance window that has not ended yet
+	maintenance: Mutex<Vec<(Instant, Instant)>>,
+	maintenance_ended: Notify,
+	// The most recent reconnect episodes, oldest first
+	history: Mutex<VecDeque<ReconnectEpisode>>,
+}
+
+impl Internal {
+	/// Puts a new connection in place.
+	fn set_connected(&self, status: &mut Status, connection: SingleConnection) {
+		*self.shutdown.lock().unwrap_or_else(|e| e.into_inner()) = Some(connection.shutdown_handle());
+		*status = Connected(Box::new(connection));
+	}
+
+	/// Finishes up after a new connection has been put in place, emitting the matching event.
+	async fn reconnected(&self, instance: Option<String>) {
+		self.end_outage(false).await;
+		let previous = mem::replace(&mut *self.instance.lock().await, instance.clone());
+		let _ = self.events.send(match previous == instance {
+			true => Event::Reconnected,
+			false => Event::ServerRestarted,
+		});
+	}
+
+	/// Records when the reconnect loop will make its next attempt, or `None` if an attempt is in progress.
+	async fn schedule_attempt(&self, delay: Option<Duration>) {
+		if let Some(outage) = &mut *self.outage.lock().await {
+			outage.next_attempt = delay.map(|delay| Instant::now() + delay);
+		}
+	}
+
+	/// Records the start of an outage caused by `e`.
+	async fn start_outage(&self, e: &RconError) {
+		*self.outage.lock().await = Some(Outage {
+			since: Instant::now(),
+			next_attempt: None,
+			cause: e.to_string(),
+			code: e.code(),
+			attempts: 0,
+		});
+	}
+
+	/// Ends the current outage, if any, adding it to the reconnect history.
+	async fn end_outage(&self, gave_up: bool) {
+		let outage = match self.outage.lock().await.take() {
+			Some(outage) => outage,
+			None => return,
+		};
+		let mut history = self.history.lock().await;
+		if history.len() == HISTORY_LEN {
+			history.pop_front();
+		}
+		history.push_back(ReconnectEpisode {
+			cause: outage.cause,
+			code: outage.code,
+			since: outage.since,
+			downtime: outage.since.elapsed(),
+			attempts: outage.attempts,
+			gave_up,
+		});
+	}
+
+	/// Closes the connection and the standby connection, and tells the reconnect loop to stop.
+	async fn stop(&self) {
+		// Shutting down the connection first fails any exec in progress, which would otherwise keep the status
+		let shutdown = self.shutdown.lock().unwrap_or_else(|e| e.into_inner()).take();
+		if let Some(shutdown) = shutdown {
+			shutdown.shutdown().await;
+		}
+
+		{
+			let mut lock = self.status.lock().await;
+			if let Connected(connection) = mem::replace(&mut *lock, Status::Stopped) {
+				connection.close().await;
+			}
+		}
+
+		let standby = self.standby.lock().await.take();
+		if let Some((standby, _)) = standby {
+			standby.close().await;
+		}
+
+		self.close_connection.notify_one();
+	}
+
+	/// Returns the end of the maintenance window the current time falls in, if any, forgetting windows that have ended.
+	async fn maintenance_end(&self) -> Option<Instant> {
+		let now = Instant::now();
+		let mut windows = self.maintenance.lock().await;
+		windows.retain(|(_, end)| *end > now);
+		windows
+			.iter()
+			.filter(|(start, _)| *start <= now)
+			.map(|(_, end)| *end)
+			.max()
+	}
+
+	/// Waits for the current maintenance window to end, if there is one.
+	async fn wait_for_maintenance(&self) {
+		while let Some(end) = self.maintenance_end().await {
+			let ended = self.maintenance_ended.notified();
+			select! {
+				_ = sleep_until(end) => (),
+				_ = ended => (),
+			};
+		}
+	}
+
+	/// Registers a disconnect or failed reconnect attempt, opening the circuit breaker if the threshold is reached.
+	async fn record_failure(&self, settings: &Settings) {
+		if let Some(circuit_breaker) = &settings.circuit_breaker {
+			let mut breaker = self.breaker.lock().await;
+			breaker.failures += 1;
+			if breaker.failures >= circuit_breaker.failure_threshold {
+				breaker.open_until = Some(Instant::now() + circuit_breaker.cooldown);
+			}
+		}
+	}
+
+	/// Returns the remaining cool-down if the circuit breaker is currently open.
+	async fn cooldown_remaining(&self) -> Option<Duration> {
+		let breaker = self.breaker.lock().await;
+		breaker
+			.open_until
+			.and_then(|until| until.checked_duration_since(Instant::now()))
+	}
+}
+
+struct Outage {
+	since: Instant,
+	next_attempt: Option<Instant>,
+	cause: String,
+	code: &'static str,
+	attempts: u32,
+}
+
+/// State of the circuit breaker, which is half-open once `open_until` has passed while `failures` is still at the
+/// threshold: a single reconnect attempt is allowed, and any further failure opens it again.
+#[derive(Default)]
+struct Breaker {
+	// Failures since the last successful exec
+	failures: u32,
+	open_until: Option<Instant>,
+}
+
+/// Events emitted by [`ReConnection`](struct.ReConnection.html), which can be received through
+/// [`ReConnection::events`](struct.ReConnection.html#method.events).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+	/// The connection was lost and reconnecting has started, with a `to_string` representation of the error that
+	/// caused it.
+	Disconnected(String),
+	/// An attempt to reconnect failed, after which another attempt will be made.
+	ReconnectFailed {
+		/// A `to_string` representation of the error the attempt failed with.
+		error: String,
+		/// The [`code`](enum.Error.html#method.code) of that error, to tell apart an unreachable server from one
+		/// that rejects the password.
+		code: &'static str,
+		/// How long the attempt took before failing.
+		duration: Duration,
+	},
+	/// The [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy) gave up on reconnecting, with a
+	/// `to_string` representation of the error of the last attempt.
+	GaveUp(String),
+	/// The connection has been re-established.
+	Reconnected,
+	/// The connection has been re-established, but the
+	/// [`restart_probe`](struct.Settings.html#structfield.restart_probe) returned a different response than before,
+	/// meaning the server has restarted in the meantime.
+	ServerRestarted,
+	/// A background task panicked, with the panic message. A panicking connection is reconnected, and a panic while
+	/// reconnecting starts reconnecting over, so this is followed by the usual events.
+	InternalError(String),
+}
+
+/// Current state of a [`ReConnection`](struct.ReConnection.html), as returned by
+/// [`ReConnection::status`](struct.ReConnection.html#method.status).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionStatus {
+	/// The connection is up.
+	Connected,
+	/// The connection was lost and is being re-established.
+	Reconnecting {
+		/// A `to_string` representation of the error that caused the disconnect.
+		reason: String,
+		/// How long the connection has been down.
+		down_for: Duration,
+		/// How long until the next reconnect attempt, or `None` if an attempt is currently in progress.
+		next_attempt_in: Option<Duration>,
+	},
+	/// Reconnecting was given up on by the [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy).
+	GaveUp {
+		/// A `to_string` representation of the error of the last reconnect attempt.
+		reason: String,
+	},
+}
+
+/// A past outage of a [`ReConnection`](struct.ReConnection.html), from losing the connection until it was
+/// re-established or given up on, as returned by
+/// [`ReConnection::reconnect_history`](struct.ReConnection.html#method.reconnect_history).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReconnectEpisode {
+	/// A `to_string` representation of the error that caused the disconnect.
+	pub cause: String,
+	/// The [`code`](enum.Error.html#method.code) of that error.
+	pub code: &'static str,
+	/// When the connection was lost.
+	pub since: Instant,
+	/// How long the connection was down.
+	pub downtime: Duration,
+	/// How many reconnect attempts were made, including the last one. Zero if a
+	/// [`warm_standby`](struct.Settings.html#structfield.warm_standby) connection took over right away.
+	pub attempts: u32,
+	/// Whether the [`reconnect_policy`](struct.Settings.html#structfield.reconnect_policy) gave up on reconnecting.
+	pub gave_up: bool,
 }
 
 /// Drop-in replacement wrapper of [`Connection`](struct.Connection.html) which intercepts all [`IO errors`](enum.Error.html#variant.IO)
@@ -36,6 +293,22 @@ pub struct ReconnectingConnection {
 
 	internal: Arc<Internal>,
 	reconnect_loop: Option<JoinHandle<()>>,
+	standby_loop: Option<JoinHandle<()>>,
+}
+
+impl Debug for ReconnectingConnection {
+	/// Shows the current connection as well, unless its status is locked at the moment. The password is redacted.
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let mut debug = f.debug_struct("ReConnection");
+		debug
+			.field("address", &self.address)
+			.field("pass", &format_args!("<redacted>"));
+		match self.internal.status.try_lock() {
+			Ok(status) => debug.field("status", &*status),
+			Err(_) => debug.field("status", &format_args!("<busy>")),
+		};
+		debug.field("settings", &self.settings).finish()
+	}
 }
 
 impl ReconnectingConnection {
@@ -43,56 +316,504 @@ impl ReconnectingConnection {
 	pub async fn open(address: impl ToString, pass: impl ToString, settings: Settings) -> Result<Self, RconError> {
 		let address = address.to_string();
 		let pass = pass.to_string();
-		let status = Mutex::new(Connected(
-			SingleConnection::open(address.clone(), pass.clone(), settings.clone()).await?,
-		));
+		let (connection, instance) = Self::connect(address.clone(), pass.clone(), settings.clone()).await?;
 		let internal = Arc::new(Internal {
-			status,
+			shutdown: std::sync::Mutex::new(Some(connection.shutdown_handle())),
+			status: Mutex::new(Connected(Box::new(connection))),
 			close_connection: Notify::new(),
+			events: broadcast::channel(16).0,
+			instance: Mutex::new(instance),
+			breaker: Mutex::new(Breaker::default()),
+			outage: Mutex::new(None),
+			standby: Mutex::new(None),
+			maintenance: Mutex::new(Vec::new()),
+			maintenance_ended: Notify::new(),
+			history: Mutex::new(VecDeque::new()),
 		});
-		Ok(ReconnectingConnection {
+		let mut connection = ReconnectingConnection {
 			address,
 			pass,
 			settings,
 			internal,
 			reconnect_loop: None,
-		})
+			standby_loop: None,
+		};
+		connection.start_standby();
+		Ok(connection)
 	}
 
 	/// This function behaves identical to [`Connection::exec`](struct.Connection.html#method.exec) unless `Err([IO](enum.Error.html#variant.IO))` is returned,
 	/// in which case it will start reconnecting and return [`BusyReconnecting`](enum.Error.html#variant.BusyReconnecting) until the connection has been re-established.
 	pub async fn exec(&mut self, cmd: impl ToString) -> Result<String, RconError> {
-		// First, we check if we are actively reconnecting, this must be done within a Mutex
+		self.exec_raw_bytes(cmd.to_string().as_bytes()).await
+	}
+
+	/// This function behaves identical to [`Connection::exec_str`](struct.Connection.html#method.exec_str),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_str(&mut self, cmd: &str) -> Result<String, RconError> {
+		self.exec_raw_bytes(cmd.as_bytes()).await
+	}
+
+	/// This function behaves identical to [`Connection::exec_raw_bytes`](struct.Connection.html#method.exec_raw_bytes),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_raw_bytes(&mut self, cmd: &[u8]) -> Result<String, RconError> {
+		self.exec_impl(cmd, &mut |_| (), &mut Cutoff::default(), Interrupt::default())
+			.await
+	}
+
+	/// This function behaves identical to [`Connection::exec_raw`](struct.Connection.html#method.exec_raw),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_raw(&mut self, cmd: impl ToString) -> Result<Vec<Packet>, RconError> {
 		let result = {
-			let mut lock = self.internal.status.lock().await;
-			let connection = match lock.deref_mut() {
-				Connected(ref mut c) => c,
-				Disconnected(msg) => return Err(BusyReconnecting(msg.clone())),
-				Stopped => unreachable!("should only set Stopped state when closing connection"),
-			};
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.exec_raw(cmd).await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		self.handle_result(result).await
+	}
 
-			// If we are connected, send the request
-			connection.exec(cmd).await
+	/// This function behaves identical to [`Connection::exec_detailed`](struct.Connection.html#method.exec_detailed),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_detailed(&mut self, cmd: impl ToString) -> Result<ExecDetails, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.exec_detailed(cmd).await,
+				_ => unreachable!("checked by connected"),
+			}
 		};
+		self.handle_result(result).await
+	}
+
+	/// This function behaves identical to [`Connection::exec_with_progress`](struct.Connection.html#method.exec_with_progress),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_with_progress(
+		&mut self, cmd: impl ToString, mut on_fragment: impl FnMut(&str),
+	) -> Result<String, RconError> {
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut on_fragment,
+			&mut Cutoff::default(),
+			Interrupt::default(),
+		)
+		.await
+	}
+
+	/// This function behaves identical to [`Connection::exec_with_cutoff`](struct.Connection.html#method.exec_with_cutoff),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_with_cutoff(
+		&mut self, cmd: impl ToString, max_bytes: usize,
+	) -> Result<CutoffResponse, RconError> {
+		let mut cutoff = Cutoff::new(max_bytes);
+		let response = self
+			.exec_impl(
+				cmd.to_string().as_bytes(),
+				&mut |_| (),
+				&mut cutoff,
+				Interrupt::default(),
+			)
+			.await?;
+		Ok(cutoff.into_response(response))
+	}
+
+	/// This function behaves identical to [`Connection::exec_with_timeout`](struct.Connection.html#method.exec_with_timeout),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_with_timeout(&mut self, cmd: impl ToString, limit: Duration) -> Result<String, RconError> {
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut |_| (),
+			&mut Cutoff::default(),
+			Interrupt::timeout(limit),
+		)
+		.await
+	}
+
+	/// This function behaves identical to [`Connection::exec_cancellable`](struct.Connection.html#method.exec_cancellable),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_cancellable(
+		&mut self, cmd: impl ToString, cancel: impl Future<Output = ()> + Send,
+	) -> Result<String, RconError> {
+		tokio::pin!(cancel);
+		self.exec_impl(
+			cmd.to_string().as_bytes(),
+			&mut |_| (),
+			&mut Cutoff::default(),
+			Interrupt::cancel(cancel),
+		)
+		.await
+	}
+
+	async fn exec_impl(
+		&mut self, cmd: &[u8], on_fragment: &mut impl FnMut(&str), cutoff: &mut Cutoff, interrupt: Interrupt<'_>,
+	) -> Result<String, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.exec_impl(cmd, on_fragment, cutoff, interrupt).await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		self.handle_result(result).await
+	}
+
+	/// This function behaves identical to [`Connection::exec_no_response`](struct.Connection.html#method.exec_no_response),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_no_response(&mut self, cmd: impl ToString, grace: Option<Duration>) -> Result<String, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.exec_no_response(cmd, grace).await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		self.handle_result(result).await
+	}
 
-		// If the result is an IO error, trigger reconnection and return BusyReconnecting
-		if let Err(IO(_)) = result {
-			return Err(self.start_reconnect(result.unwrap_err()).await);
+	/// This function behaves identical to [`Connection::exec_at`](struct.Connection.html#method.exec_at), with the same
+	/// reconnection behaviour as [`exec`](#method.exec). The status is not held on to while waiting, so reconnecting
+	/// can proceed in the meantime.
+	pub async fn exec_at(&mut self, cmd: impl ToString, at: Instant) -> Result<String, RconError> {
+		let send_time = match &*self.internal.status.lock().await {
+			Connected(connection) => connection.send_time(at),
+			_ => at,
+		};
+		sleep_until(send_time).await;
+		self.exec(cmd).await
+	}
+
+	/// Locks the status, failing with the reason if there is no connection to exec on. The status must be held on
+	/// to during the exec, so no reconnect can replace the connection in the meantime.
+	async fn connected(&self) -> Result<MutexGuard<'_, Status>, RconError> {
+		if let Some(end) = self.internal.maintenance_end().await {
+			return Err(InMaintenance(end.saturating_duration_since(Instant::now())));
+		}
+		let lock = self.internal.status.lock().await;
+		match &*lock {
+			Connected(_) => Ok(lock),
+			Disconnected(msg) => Err(match self.internal.cooldown_remaining().await {
+				Some(remaining) => CircuitOpen(remaining),
+				None => BusyReconnecting(msg.clone()),
+			}),
+			GaveUp(msg) => Err(RconError::GaveUp(msg.clone())),
+			Stopped => Err(ConnectionClosing),
+		}
+	}
+
+	/// Checks the result of an exec, reconnecting if it failed because of the connection.
+	async fn handle_result<T>(&mut self, result: Result<T, RconError>) -> Result<T, RconError> {
+		// If the result is an IO error or the stream got corrupted, trigger reconnection and return BusyReconnecting
+		let result = match result {
+			Err(e @ IO(_)) | Err(e @ PartialWrite) | Err(e @ Superseded) | Err(e @ InternalError(_)) => {
+				return Err(self.start_reconnect(e).await)
+			}
+			result => result,
+		};
+
+		// The connection is healthy again, so close the circuit breaker
+		if result.is_ok() && self.settings.circuit_breaker.is_some() {
+			*self.internal.breaker.lock().await = Breaker::default();
 		}
 
 		result
 	}
 
+	/// This function behaves identical to [`Connection::exec_json`](struct.Connection.html#method.exec_json),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	#[cfg(feature = "json")]
+	pub async fn exec_json<T: serde::de::DeserializeOwned>(&mut self, cmd: impl ToString) -> Result<T, RconError> {
+		Ok(serde_json::from_str(&self.exec(cmd).await?)?)
+	}
+
+	/// This function behaves identical to [`Connection::exec_stream`](struct.Connection.html#method.exec_stream),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	#[cfg(feature = "stream")]
+	pub fn exec_stream(&mut self, cmd: impl ToString) -> ResponseStream<'_> {
+		let cmd = cmd.to_string();
+		let (sender, chunks) = std::sync::mpsc::channel();
+		let exec = async move {
+			let result = {
+				let lock = self.connected().await?;
+				match &*lock {
+					Connected(connection) => connection.exec_chunks(cmd.as_bytes(), &sender).await,
+					_ => unreachable!("checked by connected"),
+				}
+			};
+			self.handle_result(result).await
+		};
+		ResponseStream::new(exec, chunks)
+	}
+
+	/// This function behaves identical to [`Connection::exec_to_writer`](struct.Connection.html#method.exec_to_writer),
+	/// with the same reconnection behaviour as [`exec`](#method.exec). A failing writer does not cause a reconnect.
+	pub async fn exec_to_writer(
+		&mut self, cmd: impl ToString, writer: impl AsyncWrite + Unpin,
+	) -> Result<u64, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.exec_into(cmd.to_string().as_bytes(), writer).await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		Ok(self.handle_result(result).await??)
+	}
+
+	/// This function behaves identical to [`Connection::server_info`](struct.Connection.html#method.server_info),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn server_info(&mut self) -> Result<ServerInfo, RconError> {
+		let profile = self.settings.profile;
+		let response = self.exec(ServerInfo::command(profile)).await?;
+		Ok(ServerInfo::parse(profile, &response, &self.settings.localization))
+	}
+
+	/// This function behaves identical to [`Connection::kick`](struct.Connection.html#method.kick),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn kick(&mut self, player: &PlayerRef, reason: &str) -> Result<ModerationOutcome, RconError> {
+		let cmd = kick_command(self.settings.profile, player, reason)?;
+		let response = self.exec(cmd).await?;
+		Ok(ModerationOutcome::parse(response, &self.settings.localization))
+	}
+
+	/// This function behaves identical to [`Connection::ban`](struct.Connection.html#method.ban),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn ban(
+		&mut self, player: &PlayerRef, duration: Option<Duration>, reason: &str,
+	) -> Result<ModerationOutcome, RconError> {
+		let cmd = ban_command(self.settings.profile, player, duration, reason)?;
+		let response = self.exec(cmd).await?;
+		Ok(ModerationOutcome::parse(response, &self.settings.localization))
+	}
+
+	/// This function behaves identical to [`Connection::exec_all`](struct.Connection.html#method.exec_all),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn exec_all(&mut self, cmds: &[impl ToString], policy: BatchPolicy) -> Vec<Result<String, RconError>> {
+		let mut results = Vec::with_capacity(cmds.len());
+		for cmd in cmds {
+			let result = self.exec(cmd.to_string()).await;
+			let failed = result.is_err();
+			results.push(result);
+			if failed && policy == BatchPolicy::StopOnError {
+				break;
+			}
+		}
+		results
+	}
+
+	/// This function behaves identical to [`Connection::exec_pipelined`](struct.Connection.html#method.exec_pipelined),
+	/// with the same reconnection behaviour as [`exec`](#method.exec). Reconnecting starts once all commands have
+	/// finished, if any of them ran into a connection failure.
+	pub async fn exec_pipelined(&mut self, cmds: &[impl ToString], depth: usize) -> Vec<Result<String, RconError>> {
+		let results = match self.connected().await {
+			Ok(lock) => match &*lock {
+				Connected(connection) => Some(connection.exec_pipelined(cmds, depth).await),
+				_ => unreachable!("checked by connected"),
+			},
+			Err(_) => None,
+		};
+		let results = match results {
+			Some(results) => results,
+			// Every command gets the error exec fails with
+			None => return self.exec_all(cmds, BatchPolicy::Continue).await,
+		};
+
+		// Only the first failure of the connection starts reconnecting, the ones after it are likely caused by it
+		let mut handled = false;
+		let mut checked = Vec::with_capacity(results.len());
+		for result in results {
+			match result {
+				Err(e) if !handled && matches!(e, IO(_) | PartialWrite | Superseded | InternalError(_)) => {
+					handled = true;
+					checked.push(self.handle_result(Err(e)).await);
+				}
+				result => checked.push(result),
+			}
+		}
+		if !handled {
+			let _ = self.handle_result(Ok(())).await;
+		}
+		checked
+	}
+
+	/// This function behaves identical to [`Connection::paginate`](struct.Connection.html#method.paginate),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn paginate(
+		&mut self, mut command: impl FnMut(usize) -> String, mut is_last_page: impl FnMut(usize, &str) -> bool,
+	) -> Result<String, RconError> {
+		let mut result = String::new();
+		let mut previous = String::new();
+		for page in 1.. {
+			let response = self.exec(command(page)).await?;
+			if response.is_empty() || response == previous {
+				break;
+			}
+			result += &response;
+			if is_last_page(page, &response) {
+				break;
+			}
+			previous = response;
+		}
+		Ok(result)
+	}
+
+	/// This function behaves identical to
+	/// [`Connection::drain_unsolicited`](struct.Connection.html#method.drain_unsolicited). Messages buffered by a lost
+	/// connection are discarded, and nothing is returned while reconnecting.
+	pub async fn drain_unsolicited(&mut self) -> Result<Vec<String>, RconError> {
+		match &mut *self.internal.status.lock().await {
+			Connected(connection) => connection.drain_unsolicited(),
+			_ => Ok(Vec::new()),
+		}
+	}
+
+	/// Returns the capabilities of this connection, which are identical to those of
+	/// [`Connection::capabilities`](struct.Connection.html#method.capabilities) as they are determined by the
+	/// settings, and thus unaffected by reconnects.
+	pub fn capabilities(&self) -> Capabilities {
+		Capabilities::new(&self.settings)
+	}
+
+	/// This function behaves identical to [`Connection::stats`](struct.Connection.html#method.stats), counting from
+	/// the latest (re)connect. While reconnecting, all counters are zero. The reconnects themselves are counted by
+	/// [`reconnect_history`](#method.reconnect_history).
+	pub async fn stats(&self) -> Stats {
+		match &*self.internal.status.lock().await {
+			Connected(connection) => connection.stats(),
+			_ => Stats::default(),
+		}
+	}
+
+	/// Returns the last 32 outages that have ended, oldest first, with what caused them, how long they lasted and how
+	/// many attempts it took to reconnect, to tell how flaky the server has been. An ongoing outage is reported by
+	/// [`status`](#method.status) instead.
+	pub async fn reconnect_history(&self) -> Vec<ReconnectEpisode> {
+		self.internal.history.lock().await.iter().cloned().collect()
+	}
+
+	/// This function behaves identical to [`Connection::self_test`](struct.Connection.html#method.self_test),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn self_test(&mut self) -> Result<SelfTestReport, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.self_test().await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		self.handle_result(result).await
+	}
+
+	/// This function behaves identical to [`Connection::ping`](struct.Connection.html#method.ping),
+	/// with the same reconnection behaviour as [`exec`](#method.exec).
+	pub async fn ping(&mut self) -> Result<Duration, RconError> {
+		let result = {
+			let lock = self.connected().await?;
+			match &*lock {
+				Connected(connection) => connection.ping().await,
+				_ => unreachable!("checked by connected"),
+			}
+		};
+		self.handle_result(result).await
+	}
+
+	/// This function behaves identical to [`Connection::round_trip_time`](struct.Connection.html#method.round_trip_time),
+	/// measuring from the latest (re)connect. While reconnecting, it is `None`.
+	pub async fn round_trip_time(&self) -> Option<Duration> {
+		match &*self.internal.status.lock().await {
+			Connected(connection) => connection.round_trip_time(),
+			_ => None,
+		}
+	}
+
+	/// This function behaves identical to [`Connection::peer_addr`](struct.Connection.html#method.peer_addr), returning
+	/// the address of the latest (re)connect, which may differ from before if the address resolves to several. While
+	/// reconnecting, it is `None`.
+	pub async fn peer_addr(&self) -> Option<SocketAddr> {
+		match &*self.internal.status.lock().await {
+			Connected(connection) => Some(connection.peer_addr()),
+			_ => None,
+		}
+	}
+
+	/// This function behaves identical to [`Connection::local_addr`](struct.Connection.html#method.local_addr). While
+	/// reconnecting, it is `None`.
+	pub async fn local_addr(&self) -> Option<SocketAddr> {
+		match &*self.internal.status.lock().await {
+			Connected(connection) => Some(connection.local_addr()),
+			_ => None,
+		}
+	}
+
+	/// This function behaves identical to [`Connection::connected_since`](struct.Connection.html#method.connected_since),
+	/// returning when the latest (re)connect completed. While reconnecting, it is `None`.
+	pub async fn connected_since(&self) -> Option<Instant> {
+		match &*self.internal.status.lock().await {
+			Connected(connection) => Some(connection.connected_since()),
+			_ => None,
+		}
+	}
+
+	/// Returns whether the connection is currently up, or for how long it has been down and when the next reconnect
+	/// attempt is scheduled, for example to inform users about an outage.
+	pub async fn status(&self) -> ConnectionStatus {
+		// The status is held on to, so a reconnect can't end the outage while it is being read
+		let status = self.internal.status.lock().await;
+		let reason = match &*status {
+			Disconnected(reason) => reason.clone(),
+			GaveUp(reason) => return ConnectionStatus::GaveUp { reason: reason.clone() },
+			_ => return ConnectionStatus::Connected,
+		};
+		let now = Instant::now();
+		let outage = self.internal.outage.lock().await;
+		let outage = outage.as_ref().expect("an outage is recorded before disconnecting");
+		ConnectionStatus::Reconnecting {
+			reason,
+			down_for: now.saturating_duration_since(outage.since),
+			next_attempt_in: outage.next_attempt.map(|at| at.saturating_duration_since(now)),
+		}
+	}
+
+	/// Plans a maintenance window of `duration`, starting `starts_in` from now, during which the server is expected to
+	/// be down. Within the window, every exec fails with [`InMaintenance`](enum.Error.html#variant.InMaintenance) and
+	/// no reconnect attempts are made, so planned downtime doesn't show up as failures. Reconnecting resumes once the
+	/// window has ended.
+	pub async fn schedule_maintenance(&self, starts_in: Duration, duration: Duration) {
+		let start = Instant::now() + starts_in;
+		self.internal.maintenance.lock().await.push((start, start + duration));
+	}
+
+	/// Ends the current maintenance window early and cancels any planned ones.
+	pub async fn end_maintenance(&self) {
+		self.internal.maintenance.lock().await.clear();
+		self.internal.maintenance_ended.notify_waiters();
+	}
+
+	/// Subscribes to the [`Event`](enum.Event.html)s emitted from this point on, so callers can for example rerun
+	/// their initialization once the server has restarted.
+	pub fn events(&self) -> broadcast::Receiver<Event> {
+		self.internal.events.subscribe()
+	}
+
+	/// This function behaves identical to [`Connection::shutdown_signal`](struct.Connection.html#method.shutdown_signal),
+	/// also stopping the reconnect and warm standby tasks. Once shut down, every exec fails with
+	/// [`ConnectionClosing`](enum.Error.html#variant.ConnectionClosing) without reconnecting.
+	pub fn shutdown_signal(&self, signal: impl Future<Output = ()> + Send + 'static) -> JoinHandle<()> {
+		let internal = self.internal.clone();
+		tokio::spawn(async move {
+			signal.await;
+			internal.stop().await;
+		})
+	}
+
 	/// Closes the connection, joining any background tasks that were spawned to help manage it.
 	pub async fn close(mut self) {
-		{
-			let mut lock = self.internal.status.lock().await;
-			if let Connected(connection) = mem::replace(&mut *lock, Status::Stopped) {
-				connection.close().await;
-			}
+		if let Some(handle) = self.standby_loop.take() {
+			handle.abort();
+			let _ = handle.await;
 		}
+		self.internal.stop().await;
 
-		self.internal.close_connection.notify_one();
 		if let Some(handle) = self.reconnect_loop.take() {
 			handle.await.unwrap_or_else(|e| match e.is_cancelled() {
 				true => (), // Cancellation is fine.
@@ -102,11 +823,57 @@ impl ReconnectingConnection {
 	}
 
 	async fn start_reconnect(&mut self, e: RconError) -> RconError {
-		// First, we change the status, which automatically disconnects the old connection
+		if let InternalError(message) = &e {
+			let _ = self.internal.events.send(Event::InternalError(message.clone()));
+		}
+
+		// Taking the session back from another client is up to the policy, as both would keep kicking each other out
+		if let (Superseded, Some(policy)) = (&e, &self.settings.reconnect_policy) {
+			if policy(FailureKind::Superseded, &e) == ReconnectDecision::GiveUp {
+				let mut lock = self.internal.status.lock().await;
+				if let Connected(_) = *lock {
+					*lock = GaveUp(e.to_string());
+					self.internal.start_outage(&e).await;
+					self.internal.end_outage(true).await;
+					let _ = self.internal.events.send(Event::Disconnected(e.to_string()));
+					let _ = self.internal.events.send(Event::GaveUp(e.to_string()));
+				}
+				return RconError::GaveUp(e.to_string());
+			}
+		}
+
+		// Promote the standby connection if we have one, so only the failed exec notices the disconnect
+		let standby = self.internal.standby.lock().await.take();
+		if let Some((standby, instance)) = standby {
+			let mut lock = self.internal.status.lock().await;
+			if let Stopped = *lock {
+				drop(lock);
+				standby.close().await;
+				return ConnectionClosing;
+			}
+			self.internal.start_outage(&e).await;
+			self.internal.set_connected(&mut lock, standby);
+			drop(lock);
+			let _ = self.internal.events.send(Event::Disconnected(e.to_string()));
+			self.internal.reconnected(instance).await;
+			self.start_standby();
+			return BusyReconnecting(e.to_string());
+		}
+
+		// First, we change the status, which automatically disconnects the old connection. Once shut down, the exec
+		// may still have failed in the meantime, but there is nothing to reconnect.
 		{
 			let mut lock = self.internal.status.lock().await;
+			if let Stopped = *lock {
+				return ConnectionClosing;
+			}
+			self.internal.start_outage(&e).await;
 			*lock = Disconnected(e.to_string());
 		}
+		let _ = self.internal.events.send(Event::Disconnected(e.to_string()));
+		self.internal.record_failure(&self.settings).await;
+		let cooldown = self.internal.cooldown_remaining().await;
+		self.internal.schedule_attempt(cooldown).await;
 
 		self.reconnect_loop = Some(tokio::spawn(Self::reconnect_loop(
 			self.address.clone(),
@@ -115,32 +882,202 @@ impl ReconnectingConnection {
 			self.internal.clone(),
 		)));
 
-		BusyReconnecting(e.to_string())
+		match self.internal.cooldown_remaining().await {
+			Some(remaining) => CircuitOpen(remaining),
+			None => BusyReconnecting(e.to_string()),
+		}
+	}
+
+	/// Starts opening a standby connection in the background, if enabled.
+	fn start_standby(&mut self) {
+		if self.settings.warm_standby {
+			self.standby_loop = Some(tokio::spawn(Self::standby_loop(
+				self.address.clone(),
+				self.pass.clone(),
+				self.settings.clone(),
+				self.internal.clone(),
+			)));
+		}
+	}
+
+	async fn standby_loop(address: String, pass: String, settings: Settings, internal: Arc<Internal>) {
+		// The standby connection is only an optimization, so a panic just leaves the next reconnect without one
+		let standby = Self::open_standby(address, pass, settings, internal.clone());
+		if let Err(message) = catch_unwind(standby).await {
+			let _ = internal.events.send(Event::InternalError(message));
+		}
+	}
+
+	async fn open_standby(address: String, pass: String, settings: Settings, internal: Arc<Internal>) {
+		loop {
+			internal.wait_for_maintenance().await;
+			if let Ok(standby) = Self::connect(address.clone(), pass.clone(), settings.clone()).await {
+				// The status is held on to, so a shutdown can't miss the standby connection
+				let status = internal.status.lock().await;
+				match *status {
+					Stopped => {
+						drop(status);
+						standby.0.close().await;
+					}
+					_ => *internal.standby.lock().await = Some(standby),
+				}
+				return;
+			}
+			sleep(Duration::from_secs(1)).await;
+		}
 	}
 
 	async fn reconnect_loop(address: String, pass: String, settings: Settings, internal: Arc<Internal>) {
+		// A panic would otherwise leave the connection disconnected for good, so reconnecting starts over instead
 		loop {
+			let reconnect = Self::reconnect(address.clone(), pass.clone(), settings.clone(), internal.clone());
+			let message = match catch_unwind(reconnect).await {
+				Ok(()) => return,
+				Err(message) => message,
+			};
+			let _ = internal.events.send(Event::InternalError(message));
+			if !matches!(*internal.status.lock().await, Disconnected(_)) {
+				return;
+			}
+
+			internal.schedule_attempt(Some(Duration::from_secs(1))).await;
 			let close_connection = internal.close_connection.notified();
-			let connection = SingleConnection::open(address.clone(), pass.clone(), settings.clone());
 			select! {
-				Ok(c) = connection => {
+				_ = sleep(Duration::from_secs(1)) => (),
+				_ = close_connection => return,
+			};
+		}
+	}
+
+	async fn reconnect(address: String, pass: String, settings: Settings, internal: Arc<Internal>) {
+		let mut auth_failures: u32 = 0;
+		let mut jitter = Jitter::new();
+		loop {
+			// The server is expected to be down during maintenance, so attempts are paused until it ends
+			if let Some(end) = internal.maintenance_end().await {
+				internal
+					.schedule_attempt(Some(end.saturating_duration_since(Instant::now())))
+					.await;
+				let close_connection = internal.close_connection.notified();
+				select! {
+					_ = internal.wait_for_maintenance() => (),
+					_ = close_connection => return,
+				};
+			}
+
+			// Wait for the circuit breaker to become half-open before attempting to connect, plus the jitter that
+			// keeps many connections from reconnecting at the same moment
+			let jitter = settings
+				.reconnect_jitter
+				.map(|max| jitter.sample(max))
+				.unwrap_or_default();
+			let wait = internal.cooldown_remaining().await.unwrap_or_default() + jitter;
+			if !wait.is_zero() {
+				internal.schedule_attempt(Some(wait)).await;
+				let close_connection = internal.close_connection.notified();
+				select! {
+					_ = sleep(wait) => (),
+					_ = close_connection => return,
+				};
+			}
+
+			internal.schedule_attempt(None).await;
+			if let Some(outage) = &mut *internal.outage.lock().await {
+				outage.attempts += 1;
+			}
+			let close_connection = internal.close_connection.notified();
+			let connection = Self::connect(address.clone(), pass.clone(), settings.clone());
+			let started = Instant::now();
+			let result = select! {
+				result = connection => result,
+				_ = close_connection => return,
+			};
+			if let Err(e) = &result {
+				let _ = internal.events.send(Event::ReconnectFailed {
+					error: e.to_string(),
+					code: e.code(),
+					duration: started.elapsed(),
+				});
+
+				if let Some(policy) = &settings.reconnect_policy {
+					if policy(FailureKind::of(e), e) == ReconnectDecision::GiveUp {
+						let mut lock = internal.status.lock().await;
+						if let Disconnected(_) = *lock {
+							*lock = GaveUp(e.to_string());
+							internal.end_outage(true).await;
+							let _ = internal.events.send(Event::GaveUp(e.to_string()));
+						}
+						return;
+					}
+				}
+			}
+
+			let delay = match result {
+				Ok((c, instance)) => {
 					let mut lock = internal.status.lock().await;
 					match *lock {
 						Stopped => c.close().await,
 						_ => {
-							*lock = Connected(c);
+							internal.set_connected(&mut lock, c);
+							internal.reconnected(instance).await;
 						}
 					}
 					return;
-				},
-				_ = close_connection => return,
-				else => (), // Connection error
+				}
+				// Back off much longer on failed authentication, as many servers ban IPs after repeated failures
+				Err(PasswordIncorrect(_)) => {
+					auth_failures = auth_failures.saturating_add(1);
+					settings
+						.auth_failure_backoff
+						.saturating_mul(2u32.pow((auth_failures - 1).min(5)))
+						.min(MAX_AUTH_FAILURE_BACKOFF)
+				}
+				Err(_) => {
+					auth_failures = 0;
+					Duration::from_secs(1)
+				}
 			};
+			internal.record_failure(&settings).await;
+			internal.schedule_attempt(Some(delay)).await;
+
 			let close_connection = internal.close_connection.notified();
 			select! {
-				_ = sleep(Duration::from_secs(1)) => (),
+				_ = sleep(delay) => (),
 				_ = close_connection => return,
 			};
 		}
 	}
+
+	/// Opens a connection and runs the restart probe on it, if one is configured.
+	async fn connect(
+		address: String, pass: String, settings: Settings,
+	) -> Result<(SingleConnection, Option<String>), RconError> {
+		let probe = settings.restart_probe.clone();
+		let connection = SingleConnection::open(address, pass, settings).await?;
+		let instance = match probe {
+			Some(probe) => Some(connection.exec(probe).await?),
+			None => None,
+		};
+		Ok((connection, instance))
+	}
+}
+
+/// Source of the random delays between reconnect attempts, sampled anew for every attempt.
+struct Jitter(u64);
+
+impl Jitter {
+	fn new() -> Self {
+		// Every RandomState is seeded differently, so connections don't share a sequence
+		Self(RandomState::new().build_hasher().finish())
+	}
+
+	/// Returns a random duration between zero and `max`, using the splitmix64 generator.
+	fn sample(&mut self, max: Duration) -> Duration {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut random = self.0;
+		random = (random ^ (random >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		random = (random ^ (random >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		random ^= random >> 31;
+		max.mul_f64(random as f64 / u64::MAX as f64)
+	}
 }
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use crate::{
+	connection::{Cutoff, Interrupt, Profile, SingleConnection},
+	error::RconError::{self, InternalError, PartialWrite, Superseded, UTFEncoding, IO},
+};
+
+// How long each check may take, unless a response_timeout is configured
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Text the encoding check has echoed back, covering two- to four-byte UTF-8 sequences
+const ENCODING_SAMPLE: &str = "rercon ÄÖÜ € 😀";
+
+/// The outcome of [`self_test`](struct.Connection.html#method.self_test), with a check for every part of the protocol
+/// that nonconformant servers tend to get wrong.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+	/// Whether the server answers the [`terminator`](struct.Settings.html#structfield.terminator), which every exec
+	/// relies on to detect the end of a response. If not, every exec hangs until it times out.
+	pub terminator: SelfTestCheck,
+	/// Whether a response spanning several packets is reassembled, using a command with a long response suited to the
+	/// [`profile`](struct.Settings.html#structfield.profile): `cvarlist` on Source engine games and `help` on Minecraft.
+	pub multi_packet: SelfTestCheck,
+	/// Whether non-ASCII text survives the round trip, by echoing it on Source engine games. On other games, this only
+	/// fails if a response of the other checks was not valid UTF-8.
+	pub encoding: SelfTestCheck,
+}
+
+/// The outcome of a single check of a [`SelfTestReport`](struct.SelfTestReport.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelfTestCheck {
+	/// The server behaved as expected.
+	Passed,
+	/// The check could not tell, such as when the response fit in a single packet, containing the reason.
+	Inconclusive(String),
+	/// The server misbehaved, containing a description of what happened.
+	Failed(String),
+}
+
+impl SelfTestReport {
+	/// Returns whether none of the checks failed.
+	pub fn passed(&self) -> bool {
+		[&self.terminator, &self.multi_packet, &self.encoding]
+			.iter()
+			.all(|check| !matches!(check, SelfTestCheck::Failed(_)))
+	}
+}
+
+impl SingleConnection {
+	/// Checks whether the server works with the [`Settings`](struct.Settings.html) of this connection, by running the
+	/// terminator as a command, a command with a long response and, on Source engine games, an `echo` of non-ASCII
+	/// text. Meant to be run right after opening a connection to a new server, so a misconfigured quirk shows up
+	/// immediately rather than as commands that fail subtly later.
+	///
+	/// Each check gives up after the [`response_timeout`](struct.Settings.html#structfield.response_timeout), or 5
+	/// seconds if none is set. Failing checks are reported rather than returned as errors, only errors that leave the
+	/// connection unusable (such as [`IO errors`](enum.Error.html#variant.IO)) are returned. In a
+	/// [`dry_run`](struct.Settings.html#structfield.dry_run), every check is inconclusive.
+	pub async fn self_test(&self) -> Result<SelfTestReport, RconError> {
+		if self.settings().dry_run {
+			let skipped = SelfTestCheck::Inconclusive("nothing is sent in a dry run".to_string());
+			return Ok(SelfTestReport {
+				terminator: skipped.clone(),
+				multi_packet: skipped.clone(),
+				encoding: skipped,
+			});
+		}
+		let limit = self.settings().response_timeout.unwrap_or(CHECK_TIMEOUT);
+		let mut invalid_utf8 = None;
+
+		let terminator = match self.check(&self.settings().terminator, limit).await? {
+			Ok(_) => SelfTestCheck::Passed,
+			Err(e) => {
+				if let UTFEncoding(_) = e {
+					invalid_utf8 = Some(e.to_string());
+				}
+				SelfTestCheck::Failed(format!("running the terminator failed: {}", e))
+			}
+		};
+
+		let profile = self.settings().profile;
+		let multi_packet = match long_command(profile) {
+			Some(cmd) => match self.check(cmd, limit).await? {
+				Ok((_, 1)) => SelfTestCheck::Inconclusive(format!("the response to `{}` fit in a single packet", cmd)),
+				Ok(_) => SelfTestCheck::Passed,
+				Err(e) => {
+					if let UTFEncoding(_) = e {
+						invalid_utf8 = Some(e.to_string());
+					}
+					SelfTestCheck::Failed(format!("`{}` failed: {}", cmd, e))
+				}
+			},
+			None => SelfTestCheck::Inconclusive(format!("no command with a long response is known for {:?}", profile)),
+		};
+
+		let encoding = match (profile, invalid_utf8) {
+			(Profile::Source, _) => match self.check(&format!("echo {}", ENCODING_SAMPLE), limit).await? {
+				Ok((response, _)) if response.contains(ENCODING_SAMPLE) => SelfTestCheck::Passed,
+				Ok((response, _)) => SelfTestCheck::Failed(format!("non-ASCII text was echoed as {:?}", response)),
+				Err(e) => SelfTestCheck::Failed(format!("`echo` failed: {}", e)),
+			},
+			(_, Some(e)) => SelfTestCheck::Failed(e),
+			(profile, None) => {
+				SelfTestCheck::Inconclusive(format!("no command to echo text is known for {:?}", profile))
+			}
+		};
+
+		Ok(SelfTestReport {
+			terminator,
+			multi_packet,
+			encoding,
+		})
+	}
+
+	/// Runs a command of the self test, returning its response and the amount of packets it was made up of. Only
+	/// errors that leave the connection unusable are returned as such, any other error is what the check found.
+	async fn check(&self, cmd: &str, limit: Duration) -> Result<Result<(String, usize), RconError>, RconError> {
+		let mut packets = 0;
+		let result = self
+			.exec_impl(
+				cmd.as_bytes(),
+				&mut |_| packets += 1,
+				&mut Cutoff::default(),
+				Interrupt::timeout(limit),
+			)
+			.await;
+		match result {
+			Err(e @ IO(_)) | Err(e @ PartialWrite) | Err(e @ Superseded) | Err(e @ InternalError(_)) => Err(e),
+			result => Ok(result.map(|response| (response, packets))),
+		}
+	}
+}
+
+/// A command with a response long enough to be split over several packets.
+fn long_command(profile: Profile) -> Option<&'static str> {
+	match profile {
+		Profile::Source => Some("cvarlist"),
+		Profile::Minecraft => Some("help"),
+		Profile::Ark | Profile::Generic => None,
+	}
+}
@@ -0,0 +1,196 @@
+//! Helpers for Source engine (Half-Life log format) servers, such as CS:GO, CS2 and Team Fortress 2.
+
+use crate::id::SteamId;
+
+/// A player as it appears in log lines: `"Name<user id><steam id><team>"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogPlayer {
+	/// The player's display name.
+	pub name: String,
+	/// The user id assigned by the server for this session.
+	pub user_id: i32,
+	/// The player's Steam ID (`STEAM_1:0:1234`, `[U:1:1234]`), or `BOT`/`Console`.
+	pub steam_id: String,
+	/// The player's team, empty if the player has not joined one.
+	pub team: String,
+}
+
+impl LogPlayer {
+	/// The parsed [`steam_id`](#structfield.steam_id), or `None` for bots and the console.
+	pub fn steam_account(&self) -> Option<SteamId> {
+		self.steam_id.parse().ok()
+	}
+}
+
+/// A typed event parsed from a log line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogEvent {
+	/// A chat message, `team_only` being set for `say_team`.
+	Chat {
+		/// The player who sent the message.
+		player: LogPlayer,
+		/// Whether the message was only sent to the player's team.
+		team_only: bool,
+		/// The contents of the message.
+		message: String,
+	},
+	/// A player killed another player.
+	Kill {
+		/// The player who made the kill.
+		killer: LogPlayer,
+		/// The player who was killed.
+		victim: LogPlayer,
+		/// The weapon used.
+		weapon: String,
+	},
+	/// A player connected to the server.
+	Connected {
+		/// The player who connected.
+		player: LogPlayer,
+		/// The address the player connected from.
+		address: String,
+	},
+	/// A player disconnected from the server.
+	Disconnected {
+		/// The player who disconnected.
+		player: LogPlayer,
+		/// The reason given for the disconnect.
+		reason: String,
+	},
+	/// A player joined a team.
+	JoinedTeam {
+		/// The player who switched teams.
+		player: LogPlayer,
+		/// The team the player joined.
+		team: String,
+	},
+	/// A team-wide event, such as `Team "CT" triggered "SFUI_Notice_CTs_Win"`.
+	Team {
+		/// The name of the team.
+		team: String,
+		/// The remainder of the line describing the event.
+		event: String,
+	},
+	/// Any other line, containing the message after the timestamp.
+	Other(String),
+}
+
+/// A single parsed log line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLine {
+	/// The timestamp of the line as written by the server, such as `10/17/2026 - 12:34:56`.
+	pub timestamp: String,
+	/// The event described by the line.
+	pub event: LogEvent,
+}
+
+/// Parses a single line in the standard Half-Life log format, as received from a log file or `logaddress`
+/// forwarding. Returns `None` if the line does not start with an `L <timestamp>: ` prefix.
+pub fn parse_log_line(line: &str) -> Option<LogLine> {
+	let line = line.trim_end().strip_prefix("L ")?;
+	let (timestamp, message) = line.split_once(": ")?;
+
+	Some(LogLine {
+		timestamp: timestamp.to_string(),
+		event: parse_event(message).unwrap_or_else(|| LogEvent::Other(message.to_string())),
+	})
+}
+
+fn parse_event(message: &str) -> Option<LogEvent> {
+	if let Some(rest) = message.strip_prefix("Team ") {
+		let (team, event) = quoted(rest)?;
+		return Some(LogEvent::Team {
+			team,
+			event: event.trim_start().to_string(),
+		});
+	}
+
+	let (player, rest) = player(message)?;
+	let rest = rest.trim_start();
+	if let Some(rest) = rest.strip_prefix("say_team ") {
+		let (message, _) = quoted(rest)?;
+		Some(LogEvent::Chat {
+			player,
+			team_only: true,
+			message,
+		})
+	} else if let Some(rest) = rest.strip_prefix("say ") {
+		let (message, _) = quoted(rest)?;
+		Some(LogEvent::Chat {
+			player,
+			team_only: false,
+			message,
+		})
+	} else if let Some(rest) = rest.strip_prefix("connected, address ") {
+		let (address, _) = quoted(rest)?;
+		Some(LogEvent::Connected { player, address })
+	} else if let Some(rest) = rest.strip_prefix("disconnected (reason ") {
+		let (reason, _) = quoted(rest)?;
+		Some(LogEvent::Disconnected { player, reason })
+	} else if let Some(rest) = rest.strip_prefix("joined team ") {
+		let (team, _) = quoted(rest)?;
+		Some(LogEvent::JoinedTeam { player, team })
+	} else if let Some(rest) = rest.strip_prefix("switched from team ") {
+		// CS:GO and CS2: switched from team <Unassigned> to <CT>
+		let team = rest.rsplit_once("to <")?.1.strip_suffix('>')?;
+		Some(LogEvent::JoinedTeam {
+			player,
+			team: team.to_string(),
+		})
+	} else {
+		// Newer games include coordinates after both players: [x y z] killed "Victim<..>" [x y z] with "weapon"
+		let rest = skip_coordinates(rest).strip_prefix("killed ")?;
+		let (victim, rest) = self::player(rest)?;
+		let rest = skip_coordinates(rest.trim_start()).strip_prefix("with ")?;
+		let (weapon, _) = quoted(rest)?;
+		Some(LogEvent::Kill {
+			killer: player,
+			victim,
+			weapon,
+		})
+	}
+}
+
+/// Parses a leading `"Name<uid><steamid><team>"` token, returning it and the remainder of the line.
+fn player(s: &str) -> Option<(LogPlayer, &str)> {
+	let s = s.strip_prefix('"')?;
+	// The token ends at the first `>"` that is followed by whitespace or the end of the line.
+	let end = s
+		.match_indices(">\"")
+		.map(|(i, _)| i)
+		.find(|&i| i + 2 == s.len() || s[i + 2..].starts_with(char::is_whitespace))?;
+	let (token, rest) = (&s[..=end], &s[end + 2..]);
+
+	// The name may contain angle brackets itself, so we parse the three fields from the end.
+	let mut fields = Vec::with_capacity(3);
+	let mut token = token;
+	for _ in 0..3 {
+		let start = token.rfind('<')?;
+		fields.push(token[start + 1..token.len() - 1].to_string());
+		token = &token[..start];
+	}
+
+	Some((
+		LogPlayer {
+			name: token.to_string(),
+			user_id: fields[2].parse().ok()?,
+			steam_id: fields[1].clone(),
+			team: fields[0].clone(),
+		},
+		rest,
+	))
+}
+
+/// Parses a leading quoted string, returning its contents and the remainder of the line.
+fn quoted(s: &str) -> Option<(String, &str)> {
+	let s = s.strip_prefix('"')?;
+	let end = s.find('"')?;
+	Some((s[..end].to_string(), &s[end + 1..]))
+}
+
+fn skip_coordinates(s: &str) -> &str {
+	match s.strip_prefix('[').and_then(|s| s.split_once(']')) {
+		Some((_, rest)) => rest.trim_start(),
+		None => s,
+	}
+}
@@ -0,0 +1,144 @@
+use std::{
+	collections::VecDeque,
+	mem,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+
+use tokio::{select, sync::mpsc};
+
+use crate::{connection::SingleConnection, error::RconError};
+
+/// The response to a command sent through a [`CommandSender`](struct.CommandSender.html).
+#[derive(Debug)]
+pub struct Response {
+	/// The ticket [`send`](struct.CommandSender.html#method.send) returned for the command.
+	pub ticket: u64,
+	/// The command, as it was sent.
+	pub command: String,
+	/// The response, or the error the command failed with.
+	pub result: Result<String, RconError>,
+}
+
+/// Something received by a [`ResponseReceiver`](struct.ResponseReceiver.html).
+#[derive(Debug)]
+pub enum Incoming {
+	/// The response to a command.
+	Response(Response),
+	/// A message the server sent outside of a request, only received with an
+	/// [`unsolicited`](struct.Settings.html#structfield.unsolicited) buffer configured. An
+	/// [`UnsolicitedOverflow`](enum.Error.html#variant.UnsolicitedOverflow) reports messages that didn't fit the
+	/// buffer.
+	Unsolicited(Result<String, RconError>),
+}
+
+/// The sending half of a [`split`](struct.Connection.html#method.split) connection, which sends commands without
+/// waiting for their responses. Can be cloned to send from several tasks.
+#[derive(Clone)]
+pub struct CommandSender {
+	connection: Arc<SingleConnection>,
+	tickets: Arc<AtomicU64>,
+	responses: mpsc::UnboundedSender<Response>,
+}
+
+/// The receiving half of a [`split`](struct.Connection.html#method.split) connection, receiving the responses to
+/// the commands of the [`CommandSender`](struct.CommandSender.html) in the order they arrive, along with any
+/// unsolicited messages.
+pub struct ResponseReceiver {
+	connection: Arc<SingleConnection>,
+	responses: mpsc::UnboundedReceiver<Response>,
+	unsolicited: VecDeque<String>,
+	// Set when the unsolicited buffer may have something to drain
+	drain: bool,
+}
+
+impl SingleConnection {
+	/// Splits the connection into a sending and a receiving half, so sending commands and handling their responses
+	/// can happen in separate tasks, like `TcpStream::into_split`. The connection is closed once both halves are
+	/// dropped and every command sent has been answered.
+	///
+	/// # Example
+	/// ```rust,no_run
+	/// use rercon::{Connection, Incoming, Settings};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let connection = Connection::open("123.456.789.123:27020", "my_secret_password", Settings::default()).await.unwrap();
+	///     let (sender, mut receiver) = connection.split();
+	///     tokio::spawn(async move {
+	///         while let Some(Incoming::Response(response)) = receiver.recv().await {
+	///             println!("{}: {:?}", response.command, response.result);
+	///         }
+	///     });
+	///     sender.send("SaveWorld");
+	/// }
+	/// ```
+	pub fn split(self) -> (CommandSender, ResponseReceiver) {
+		let connection = Arc::new(self);
+		let (sender, receiver) = mpsc::unbounded_channel();
+		let sender = CommandSender {
+			connection: connection.clone(),
+			tickets: Arc::new(AtomicU64::new(0)),
+			responses: sender,
+		};
+		let receiver = ResponseReceiver {
+			connection,
+			responses: receiver,
+			unsolicited: VecDeque::new(),
+			// Messages may have arrived before splitting
+			drain: true,
+		};
+		(sender, receiver)
+	}
+}
+
+impl CommandSender {
+	/// Sends a command like [`exec`](struct.Connection.html#method.exec) in a newly spawned task, returning a ticket
+	/// identifying its [`Response`](struct.Response.html) right away. Tickets count up from zero.
+	pub fn send(&self, cmd: impl ToString) -> u64 {
+		let ticket = self.tickets.fetch_add(1, Ordering::Relaxed);
+		let command = cmd.to_string();
+		let connection = self.connection.clone();
+		let responses = self.responses.clone();
+		tokio::spawn(async move {
+			let result = connection.exec(&command).await;
+			// Nobody is left to care about the response if the receiver was dropped
+			let _ = responses.send(Response {
+				ticket,
+				command,
+				result,
+			});
+		});
+		ticket
+	}
+}
+
+impl ResponseReceiver {
+	/// Waits for the next response or unsolicited message. Returns `None` once every
+	/// [`CommandSender`](struct.CommandSender.html) has been dropped and all of their commands have been answered.
+	pub async fn recv(&mut self) -> Option<Incoming> {
+		loop {
+			if let Some(message) = self.unsolicited.pop_front() {
+				return Some(Incoming::Unsolicited(Ok(message)));
+			}
+			if mem::take(&mut self.drain) {
+				match self.connection.drain_unsolicited() {
+					Ok(messages) => self.unsolicited.extend(messages),
+					Err(e) => {
+						// The messages that did fit are drained next time
+						self.drain = true;
+						return Some(Incoming::Unsolicited(Err(e)));
+					}
+				}
+				continue;
+			}
+
+			select! {
+				response = self.responses.recv() => return response.map(Incoming::Response),
+				_ = self.connection.unsolicited_arrived() => self.drain = true,
+			}
+		}
+	}
+}
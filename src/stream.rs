@@ -0,0 +1,60 @@
+use std::{
+	future::Future,
+	pin::Pin,
+	sync::mpsc,
+	task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::error::RconError;
+
+type Exec<'a> = Pin<Box<dyn Future<Output = Result<(), RconError>> + Send + 'a>>;
+
+/// The response to [`exec_stream`](struct.Connection.html#method.exec_stream), yielding the body of every response
+/// packet as it arrives. The command only makes progress while the stream is polled, so a slow consumer slows down
+/// reading the response rather than having it pile up in memory.
+///
+/// Once an error is yielded, the stream ends.
+pub struct ResponseStream<'a> {
+	// Unset once the exec has finished
+	exec: Option<Exec<'a>>,
+	chunks: mpsc::Receiver<String>,
+	error: Option<RconError>,
+}
+
+impl<'a> ResponseStream<'a> {
+	/// Wraps an exec which passes the chunks of the response to the sender belonging to `chunks`.
+	pub(crate) fn new(
+		exec: impl Future<Output = Result<(), RconError>> + Send + 'a, chunks: mpsc::Receiver<String>,
+	) -> Self {
+		Self {
+			exec: Some(Box::pin(exec)),
+			chunks,
+			error: None,
+		}
+	}
+}
+
+impl Stream for ResponseStream<'_> {
+	type Item = Result<String, RconError>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		if let Some(exec) = &mut this.exec {
+			if let Poll::Ready(result) = exec.as_mut().poll(cx) {
+				this.exec = None;
+				this.error = result.err();
+			}
+		}
+
+		// Chunks that arrived before the exec failed are still handed out before the error
+		if let Ok(chunk) = this.chunks.try_recv() {
+			return Poll::Ready(Some(Ok(chunk)));
+		}
+		match this.exec {
+			Some(_) => Poll::Pending,
+			None => Poll::Ready(this.error.take().map(Err)),
+		}
+	}
+}
@@ -0,0 +1,31 @@
+use std::{
+	any::Any,
+	future::{poll_fn, Future},
+	panic::{self, AssertUnwindSafe},
+	task::Poll,
+};
+
+/// Runs a future, catching a panic while polling it and returning its message instead, so background tasks can
+/// report a bug rather than silently disappearing.
+pub(crate) async fn catch_unwind<F: Future>(future: F) -> Result<F::Output, String> {
+	let mut future = Box::pin(future);
+	poll_fn(
+		|cx| match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+			Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+			Ok(Poll::Pending) => Poll::Pending,
+			Err(payload) => Poll::Ready(Err(panic_message(payload))),
+		},
+	)
+	.await
+}
+
+/// Extracts the message from a panic payload, which is a string unless `panic_any` was used.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+	match payload.downcast::<String>() {
+		Ok(message) => *message,
+		Err(payload) => match payload.downcast::<&'static str>() {
+			Ok(message) => message.to_string(),
+			Err(_) => "panicked without a message".to_string(),
+		},
+	}
+}
@@ -0,0 +1,152 @@
+use crate::error::RconError::{self, InvalidTemplate};
+
+/// A command with named placeholders, such as `ServerChatTo {steam_id} {message}`, of which the values are escaped
+/// when filling them in. Literal braces are written as `{{` and `}}`.
+///
+/// For templates known at compile time, the [`command!`](macro.command.html) macro checks the placeholders at
+/// compile time instead.
+///
+/// # Example
+/// ```rust
+/// use rercon::CommandTemplate;
+///
+/// let template = CommandTemplate::new("ServerChatTo {steam_id} {message}").unwrap();
+/// let command = template.render(&[("steam_id", "76561198000000000"), ("message", "Hello there")]).unwrap();
+/// assert_eq!(command, r#"ServerChatTo 76561198000000000 "Hello there""#);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandTemplate {
+	parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+	Text(String),
+	Placeholder(String),
+}
+
+impl CommandTemplate {
+	/// Parses a template, failing with [`InvalidTemplate`](enum.Error.html#variant.InvalidTemplate) if it contains
+	/// unbalanced braces or a placeholder that is not a valid name.
+	pub fn new(template: &str) -> Result<Self, RconError> {
+		let mut parts = Vec::new();
+		let mut text = String::new();
+		let mut chars = template.chars().peekable();
+		while let Some(c) = chars.next() {
+			match c {
+				'{' if chars.peek() == Some(&'{') => {
+					chars.next();
+					text.push('{');
+				}
+				'}' if chars.peek() == Some(&'}') => {
+					chars.next();
+					text.push('}');
+				}
+				'{' => {
+					let mut name = String::new();
+					loop {
+						match chars.next() {
+							Some('}') => break,
+							Some(c) => name.push(c),
+							None => return Err(InvalidTemplate("unmatched {".to_string())),
+						}
+					}
+					if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+						return Err(InvalidTemplate(format!("invalid placeholder {{{}}}", name)));
+					}
+					parts.push(Part::Text(std::mem::take(&mut text)));
+					parts.push(Part::Placeholder(name));
+				}
+				'}' => return Err(InvalidTemplate("unmatched }".to_string())),
+				c => text.push(c),
+			}
+		}
+		parts.push(Part::Text(text));
+		parts.retain(|part| part != &Part::Text(String::new()));
+		Ok(Self { parts })
+	}
+
+	/// The names of the placeholders, in the order they appear in.
+	pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+		self.parts.iter().filter_map(|part| match part {
+			Part::Placeholder(name) => Some(name.as_str()),
+			Part::Text(_) => None,
+		})
+	}
+
+	/// Fills in the placeholders with the [`escape`](#method.escape)d values. Fails with
+	/// [`InvalidTemplate`](enum.Error.html#variant.InvalidTemplate) if a placeholder has no value, if a value has no
+	/// placeholder, or if a value can't be escaped.
+	pub fn render(&self, values: &[(&str, &str)]) -> Result<String, RconError> {
+		if let Some((name, _)) = values.iter().find(|(name, _)| !self.placeholders().any(|p| p == *name)) {
+			return Err(InvalidTemplate(format!("no placeholder named {}", name)));
+		}
+
+		let mut command = String::new();
+		for part in &self.parts {
+			match part {
+				Part::Text(text) => command += text,
+				Part::Placeholder(name) => match values.iter().find(|(n, _)| n == name) {
+					Some((_, value)) => command += &Self::escape(value)?,
+					None => return Err(InvalidTemplate(format!("missing value for {{{}}}", name))),
+				},
+			}
+		}
+		Ok(command)
+	}
+
+	/// Escapes a value to be used as a single argument of a command. Values containing whitespace or double quotes are
+	/// wrapped in double quotes, with any double quotes and backslashes inside escaped by a backslash. Values
+	/// containing line breaks or null characters are rejected with
+	/// [`InvalidTemplate`](enum.Error.html#variant.InvalidTemplate), as many servers would run the rest of the value
+	/// as a separate command.
+	pub fn escape(value: &str) -> Result<String, RconError> {
+		if value.contains(['\n', '\r', '\0']) {
+			return Err(InvalidTemplate(format!(
+				"value contains a line break or null character: {:?}",
+				value
+			)));
+		}
+		if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"') {
+			return Ok(value.to_string());
+		}
+
+		let mut escaped = String::with_capacity(value.len() + 2);
+		escaped.push('"');
+		for c in value.chars() {
+			if c == '"' || c == '\\' {
+				escaped.push('\\');
+			}
+			escaped.push(c);
+		}
+		escaped.push('"');
+		Ok(escaped)
+	}
+}
+
+/// Builds a command from a template like [`CommandTemplate`](struct.CommandTemplate.html), but with the placeholders
+/// checked at compile time: a placeholder without a value, or a value without a placeholder, fails to compile. The
+/// values may be anything implementing `Display`, and are escaped with
+/// [`CommandTemplate::escape`](struct.CommandTemplate.html#method.escape). Evaluates to a
+/// `Result<String, rercon::Error>`.
+///
+/// # Example
+/// ```rust
+/// let steam_id = 76561198000000000u64;
+/// let command = rercon::command!("ServerChatTo {steam_id} {message}", steam_id = steam_id, message = "Hello there");
+/// assert_eq!(command.unwrap(), r#"ServerChatTo 76561198000000000 "Hello there""#);
+/// ```
+#[macro_export]
+macro_rules! command {
+	($template:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+		// A separate function, so placeholders can't capture variables from the caller without being escaped
+		fn render($($name: &str),*) -> ::std::string::String {
+			::std::format!($template $(, $name = $name)*)
+		}
+		(|| -> ::std::result::Result<::std::string::String, $crate::Error> {
+			::std::result::Result::Ok(render($(
+				&$crate::CommandTemplate::escape(&::std::string::ToString::to_string(&$value))?
+			),*))
+		})()
+	}};
+}
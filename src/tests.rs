@@ -1,4 +1,99 @@
-use crate::packet::{Packet, TYPE_AUTH_RESPONSE, TYPE_RESPONSE};
+#[cfg(feature = "client")]
+use std::{
+	io::ErrorKind,
+	pin::Pin,
+	sync::{
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+#[cfg(feature = "client")]
+use tokio::{
+	net::{TcpListener, TcpStream},
+	time::Instant,
+};
+
+#[cfg(feature = "minecraft")]
+use crate::ServerProperties;
+#[cfg(feature = "client")]
+use crate::{
+	capabilities, check_auth,
+	packet::{MAX_PACKET_SIZE, MAX_READ_PACKET_SIZE, TYPE_AUTH},
+	probe, Connection, DiffPoller, ExecHook, Incoming, LineChanges, NewlineMode, OverflowPolicy, Profile,
+	ResponseFilter, ServerInfo, Settings, SettingsBuilder, Stats, ThrottleDetector, Transcript, UnsolicitedBuffer,
+};
+#[cfg(feature = "reconnection")]
+use crate::{
+	packet::MAX_BODY_SIZE, BatchPolicy, BoxFuture, CircuitBreaker, ConnectionStatus, Event, FailureKind, Localization,
+	ModerationOutcome, RconConnection, ReConnection, ReconnectDecision, ReconnectPolicy, SelfTestCheck, SelfTestReport,
+};
+use crate::{
+	packet::{Packet, TYPE_AUTH_RESPONSE, TYPE_EXEC, TYPE_RESPONSE},
+	CommandTemplate, EosId, Error, PlayerRef, SteamId,
+};
+#[cfg(all(feature = "client", feature = "minecraft"))]
+use crate::{PlayerEventKind, PlayerTracker};
+
+/// Spawns a minimal RCON server, replying to every exec with the output of `handler`, or dropping the connection if
+/// it returns `None`. Authentication succeeds for any password except "wrong".
+#[cfg(feature = "client")]
+async fn mock_server(handler: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let address = listener.local_addr().unwrap().to_string();
+	let handler = Arc::new(handler);
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let handler = handler.clone();
+			tokio::spawn(async move {
+				while let Ok(packet) = Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE).await {
+					let body = String::from_utf8_lossy(packet.get_body()).into_owned();
+					let reply = match packet.get_packet_type() {
+						TYPE_AUTH if body == "wrong" => Packet::new(-1, TYPE_AUTH_RESPONSE, ""),
+						TYPE_AUTH => Packet::new(packet.get_id(), TYPE_AUTH_RESPONSE, ""),
+						_ => match handler(&body) {
+							Some(reply) => Packet::new(packet.get_id(), TYPE_RESPONSE, reply),
+							None => break,
+						},
+					};
+					if reply.send_internal(Pin::new(&mut stream)).await.is_err() {
+						break;
+					}
+				}
+			});
+		}
+	});
+	address
+}
+
+/// Spawns an RCON server answering every packet, including the auth packet, with the packets returned by `script`,
+/// or dropping the connection if it returns `None`.
+#[cfg(feature = "client")]
+async fn scripted_server(script: impl Fn(&Packet) -> Option<Vec<Packet>> + Send + Sync + 'static) -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let address = listener.local_addr().unwrap().to_string();
+	let script = Arc::new(script);
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let script = script.clone();
+			tokio::spawn(async move {
+				while let Ok(packet) = Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE).await {
+					let replies = match script(&packet) {
+						Some(replies) => replies,
+						None => break,
+					};
+					for reply in replies {
+						if reply.send_internal(Pin::new(&mut stream)).await.is_err() {
+							return;
+						}
+					}
+				}
+			});
+		}
+	});
+	address
+}
 
 #[tokio::test]
 async fn packet_serialize() {
@@ -15,6 +110,70 @@ async fn packet_serialize() {
 	);
 }
 
+#[tokio::test]
+async fn packet_serialize_binary() {
+	let buf = Packet::new(1, TYPE_EXEC, vec![0xff, 0xfe, 0x01])
+		.create_packet_buffer()
+		.unwrap();
+
+	assert_eq!(buf, vec![13, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0xff, 0xfe, 0x01, 0, 0]);
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn packet_round_trip() {
+	let packet = Packet::new(-1, TYPE_AUTH_RESPONSE, "round trip");
+	let buf = packet.create_packet_buffer().unwrap();
+	let decoded = Packet::read(Pin::new(&mut buf.as_slice()), MAX_READ_PACKET_SIZE)
+		.await
+		.unwrap();
+	assert_eq!(decoded.get_id(), -1);
+	assert_eq!(decoded.get_packet_type(), TYPE_AUTH_RESPONSE);
+	assert_eq!(decoded.get_body(), b"round trip");
+}
+
+#[test]
+fn packet_encoded_len() {
+	for body in ["", "This is a test string."] {
+		let buf = Packet::new(1, TYPE_EXEC, body).create_packet_buffer().unwrap();
+		assert_eq!(buf.len(), Packet::encoded_len(body.len()));
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn packet_invalid_length() {
+	let invalid =
+		|result: Result<Packet, Error>| matches!(result, Err(Error::IO(e)) if e.kind() == ErrorKind::InvalidData);
+	for len in [-1, 0, 9, i32::MAX] {
+		let mut buf = len.to_le_bytes().to_vec();
+		buf.extend_from_slice(&[0; 16]);
+		assert!(
+			invalid(Packet::read(Pin::new(&mut buf.as_slice()), MAX_READ_PACKET_SIZE).await),
+			"{}",
+			len
+		);
+	}
+	assert!(invalid(Packet::decode_packet_buffer(4, &[0; 4])));
+	assert!(invalid(Packet::decode_packet_buffer(36, &[0; 10])));
+
+	let buf = Packet::new(1, TYPE_RESPONSE, "too long")
+		.create_packet_buffer()
+		.unwrap();
+	assert!(invalid(Packet::read(Pin::new(&mut buf.as_slice()), 17).await));
+	assert!(Packet::read(Pin::new(&mut buf.as_slice()), 18).await.is_ok());
+
+	// A packet larger than the memory limit of a connection is rejected before it is read.
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		memory_limit: Some(4),
+		..Settings::default()
+	};
+	let c = Connection::open(address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("four").await.unwrap(), "four");
+	assert!(matches!(c.exec("fives").await, Err(Error::IO(e)) if e.kind() == ErrorKind::InvalidData));
+}
+
 #[tokio::test]
 async fn packet_deserialize() {
 	let buf = vec![
@@ -24,8 +183,2377 @@ async fn packet_deserialize() {
 	let p = Packet::decode_packet_buffer(36, &buf).unwrap();
 	assert_eq!(p.get_id(), 0x77654321);
 	assert_eq!(p.get_packet_type(), TYPE_AUTH_RESPONSE);
-	assert_eq!(p.get_body(), "This is a different string");
+	assert_eq!(p.get_body(), b"This is a different string");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_newline_modes() {
+	let settings = |newline_mode| Settings {
+		newline_mode,
+		..Settings::default()
+	};
+
+	let address = mock_server(|cmd| Some(format!("[{}]", cmd))).await;
+	let c = Connection::open(address, "test", settings(NewlineMode::Split))
+		.await
+		.unwrap();
+	assert_eq!(c.exec("first\r\nsecond\n").await.unwrap(), "[first][second]");
+
+	let address = mock_server(|cmd| Some(format!("[{}]", cmd))).await;
+	let c = Connection::open(address, "test", settings(NewlineMode::Reject))
+		.await
+		.unwrap();
+	assert!(matches!(c.exec("a\nb").await, Err(Error::CommandContainsNewline)));
+	assert_eq!(c.exec("a").await.unwrap(), "[a]");
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_str() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(format!("[{}]", cmd)),
+	})
+	.await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let mut cmd = String::new();
+	for i in 0..3 {
+		cmd.clear();
+		cmd.push_str(if i % 2 == 0 {
+			"short"
+		} else {
+			"a somewhat longer command"
+		});
+		assert_eq!(c.exec_str(&cmd).await.unwrap(), format!("[{}]", cmd));
+	}
+	let too_long = "x".repeat(MAX_BODY_SIZE + 1);
+	assert!(matches!(c.exec_str(&too_long).await, Err(Error::CommandTooLong)));
+	// A rejected command leaves nothing behind in the send buffer
+	assert_eq!(c.exec_str("after").await.unwrap(), "[after]");
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.exec_str("hello").await.unwrap(), "[hello]");
+	assert!(matches!(c.exec_str("drop").await, Err(Error::BusyReconnecting(_))));
+	c.close().await;
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_with_progress() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(address, "test", Settings::default()).await.unwrap();
+	let mut fragments = Vec::new();
+	let result = c.exec_with_progress("hello", |f| fragments.push(f.to_string())).await;
+	assert_eq!(result.unwrap(), "hello");
+	assert_eq!(fragments, vec!["hello"]);
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn open_address_filter() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		address_filter: Some(Arc::new(|addr| !addr.ip().is_loopback())),
+		..Settings::default()
+	};
+	match Connection::open(address, "test", settings).await {
+		Err(Error::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied),
+		_ => panic!("expected the loopback address to be rejected"),
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn open_verify_command() {
+	let address = mock_server(|cmd| Some(format!("This is {}", cmd))).await;
+	let settings = Settings::default().verify_command("server one", "server one");
+	assert!(Connection::open(address.clone(), "test", settings).await.is_ok());
+
+	let settings = Settings::default().verify_command("server two", "server one");
+	match Connection::open(address, "test", settings).await {
+		Err(Error::VerificationFailed(response)) => assert_eq!(response, "This is server two"),
+		_ => panic!("expected verification to fail"),
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn open_rejection_reason() {
+	let address = scripted_server(|_| {
+		Some(vec![
+			Packet::new(0, TYPE_RESPONSE, "Too many connections"),
+			Packet::new(-1, TYPE_AUTH_RESPONSE, ""),
+		])
+	})
+	.await;
+
+	match Connection::open(&address, "test", Settings::default()).await {
+		Err(Error::PasswordIncorrect(reason)) => assert_eq!(reason, "Too many connections"),
+		_ => panic!("expected authentication to fail"),
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn paginate() {
+	let address = mock_server(
+		|cmd| match cmd.strip_prefix("help ").and_then(|page| page.parse::<usize>().ok()) {
+			Some(page) => Some(format!("page {} of {}\n", page.min(3), 3)),
+			None => Some(String::new()),
+		},
+	)
+	.await;
+	let c = Connection::open(address, "test", Settings::default()).await.unwrap();
+
+	let result = c
+		.paginate(|page| format!("help {}", page), |page, _| page == 2)
+		.await
+		.unwrap();
+	assert_eq!(result, "page 1 of 3\npage 2 of 3\n");
+
+	// Stops on the repeated last page, even if the predicate never does.
+	let result = c.paginate(|page| format!("help {}", page), |_, _| false).await.unwrap();
+	assert_eq!(result, "page 1 of 3\npage 2 of 3\npage 3 of 3\n");
+}
+
+#[cfg(all(feature = "client", feature = "json"))]
+#[test]
+fn settings_with_quirks() {
+	let quirks: crate::Quirks =
+		serde_json::from_str(r#"{"terminator": "echo", "newline_mode": "split", "auth_delay_ms": 0}"#).unwrap();
+	let settings = Settings::ark().with_quirks(&quirks);
+	assert_eq!(settings.terminator, "echo");
+	assert_eq!(settings.newline_mode, NewlineMode::Split);
+	assert_eq!(settings.auth_delay, None);
+	assert!(!settings.lossy_utf8);
+	assert!(serde_json::from_str::<crate::Quirks>(r#"{"unknown": true}"#).is_err());
+}
+
+#[cfg(all(feature = "client", feature = "json"))]
+#[tokio::test]
+async fn exec_json() {
+	let address = mock_server(|cmd| match cmd {
+		"players" => Some("[\"Steve\", \"Alex\"]".to_string()),
+		_ => Some("not json".to_string()),
+	})
+	.await;
+	let c = Connection::open(address, "test", Settings::default()).await.unwrap();
+	let players: Vec<String> = c.exec_json("players").await.unwrap();
+	assert_eq!(players, vec!["Steve", "Alex"]);
+	assert!(matches!(c.exec_json::<Vec<String>>("other").await, Err(Error::Json(_))));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn connection_capabilities() {
+	let defaults = capabilities();
+	assert_eq!(defaults.max_packet_size, 1024);
+	assert!(defaults.multiplexing && !defaults.source_quirks);
+	assert_eq!(
+		defaults.features.contains(&"reconnection"),
+		cfg!(feature = "reconnection")
+	);
+
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(address, "test", Settings::source()).await.unwrap();
+	assert!(c.capabilities().source_quirks);
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn connection_handle() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let handle = Connection::open(address, "test", Settings::default())
+		.await
+		.unwrap()
+		.into_handle();
+
+	let tasks: Vec<_> = (0..10)
+		.map(|i| {
+			let handle = handle.clone();
+			tokio::spawn(async move { (i, handle.exec(format!("echo {}", i)).await.unwrap()) })
+		})
+		.collect();
+	for task in tasks {
+		let (i, response) = task.await.unwrap();
+		assert_eq!(response, format!("echo {}", i));
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn multiplexed_exec() {
+	let address = mock_server(|cmd| Some(cmd.repeat(3))).await;
+	let c = Arc::new(Connection::open(address, "test", Settings::default()).await.unwrap());
+
+	let tasks: Vec<_> = (0..10)
+		.map(|i| {
+			let c = c.clone();
+			tokio::spawn(async move { (i, c.exec(format!("{} ", i)).await.unwrap()) })
+		})
+		.collect();
+	for task in tasks {
+		let (i, response) = task.await.unwrap();
+		assert_eq!(response, format!("{} ", i).repeat(3));
+	}
+	assert_eq!(c.stats().unsolicited_packets, 0);
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn shutdown_signal() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let (shutdown, signal) = tokio::sync::oneshot::channel::<()>();
+	let stopped = c.shutdown_signal(async move {
+		let _ = signal.await;
+	});
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	shutdown.send(()).unwrap();
+	stopped.await.unwrap();
+	assert!(matches!(c.exec("hello").await, Err(Error::ConnectionClosing)));
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	let stopped = c.shutdown_signal(async {});
+	stopped.await.unwrap();
+	assert!(matches!(c.exec("hello").await, Err(Error::ConnectionClosing)));
+	c.close().await;
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_cancelled_while_writing() {
+	// A server that stops reading after authenticating, with small buffers so writes start blocking soon
+	let socket = tokio::net::TcpSocket::new_v4().unwrap();
+	socket.set_recv_buffer_size(4096).unwrap();
+	socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+	let address = socket.local_addr().unwrap();
+	let listener = socket.listen(1).unwrap();
+	tokio::spawn(async move {
+		let (mut stream, _) = listener.accept().await.unwrap();
+		let auth = Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE).await.unwrap();
+		Packet::new(auth.get_id(), TYPE_AUTH_RESPONSE, "")
+			.send_internal(Pin::new(&mut stream))
+			.await
+			.unwrap();
+		tokio::time::sleep(Duration::from_secs(60)).await;
+	});
+
+	let socket = tokio::net::TcpSocket::new_v4().unwrap();
+	socket.set_send_buffer_size(4096).unwrap();
+	let stream = socket.connect(address).await.unwrap();
+	let c = Arc::new(
+		Connection::open_with_stream(stream, "test", Settings::default())
+			.await
+			.unwrap(),
+	);
+
+	// Once the buffers are full, one of these is stuck halfway through writing its packet when they are dropped
+	let execs: Vec<_> = (0..200)
+		.map(|_| {
+			let c = c.clone();
+			tokio::spawn(async move { c.exec("x".repeat(1000)).await })
+		})
+		.collect();
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	for exec in &execs {
+		exec.abort();
+	}
+	for exec in execs {
+		let _ = exec.await;
+	}
+	let result = tokio::time::timeout(Duration::from_secs(5), c.exec("hello")).await;
+	assert!(matches!(result, Ok(Err(Error::PartialWrite))));
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn shutdown_during_exec() {
+	// A server that never answers "hang"
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"hang") => vec![],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body)],
+		})
+	})
+	.await;
+
+	let c = Arc::new(Connection::open(&address, "test", Settings::default()).await.unwrap());
+	let execs: Vec<_> = (0..3)
+		.map(|_| {
+			let c = c.clone();
+			tokio::spawn(async move { c.exec("hang").await })
+		})
+		.collect();
+	// Let the commands reach the server
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	c.shutdown_signal(async {}).await.unwrap();
+	for exec in execs {
+		assert!(matches!(exec.await.unwrap(), Err(Error::ConnectionClosing)));
+	}
+	assert!(matches!(c.exec("hello").await, Err(Error::ConnectionClosing)));
+	assert_eq!(Error::ConnectionClosing.code(), "connection_closing");
+
+	// The same goes for a ReConnection, without reconnecting afterwards
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+	let shutdown = c.shutdown_signal(async {
+		let _ = rx.await;
+	});
+	let exec = tokio::spawn(async move { (c.exec("hang").await, c) });
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	tx.send(()).unwrap();
+	tokio::time::timeout(Duration::from_secs(5), shutdown)
+		.await
+		.unwrap()
+		.unwrap();
+	let (result, mut c) = exec.await.unwrap();
+	assert!(matches!(result, Err(Error::ConnectionClosing)));
+	assert!(matches!(c.exec("hello").await, Err(Error::ConnectionClosing)));
+	c.close().await;
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn server_info() {
+	let address = mock_server(|cmd| match cmd {
+		"status" => Some(
+			"hostname: Test Server\nversion : 1.38.8.1/13881 1575/8853 secure  [G:1:1234]\nmap     : de_dust2\n\
+			 players : 2 humans, 0 bots (20/0 max) (not hibernating)\n"
+				.to_string(),
+		),
+		"list" => Some("There are 2 of a max of 20 players online: alice, bob".to_string()),
+		"ListPlayers" => Some("0. Alice, 76561198000000000\n1. Bob, 76561198000000001\n".to_string()),
+		_ => Some(String::new()),
+	})
+	.await;
+
+	let c = Connection::open(&address, "test", Settings::source()).await.unwrap();
+	assert_eq!(
+		c.server_info().await.unwrap(),
+		ServerInfo {
+			name: Some("Test Server".to_string()),
+			version: Some("1.38.8.1/13881".to_string()),
+			map: Some("de_dust2".to_string()),
+			players: Some(2),
+			max_players: Some(20),
+		}
+	);
+
+	let c = Connection::open(&address, "test", Settings::minecraft()).await.unwrap();
+	let info = c.server_info().await.unwrap();
+	assert_eq!((info.players, info.max_players), (Some(2), Some(20)));
+
+	let c = Connection::open(&address, "test", Settings::ark()).await.unwrap();
+	assert_eq!(c.server_info().await.unwrap().players, Some(2));
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn moderation() {
+	let address = mock_server(|cmd| match cmd.split(' ').next().unwrap_or_default() {
+		"kick" if cmd.contains("Herobrine") => Some("No player was found".to_string()),
+		"kick" | "ban" => Some(format!("done: {}", cmd)),
+		"kickid" | "banid" => Some(format!("done: {}", cmd)),
+		"KickPlayer" => Some("Can't find player to kick".to_string()),
+		_ => Some(String::new()),
+	})
+	.await;
+	let steve = PlayerRef::Name("Steve".to_string());
+	let steam = PlayerRef::Steam(SteamId::from_account_id(22202));
+
+	let c = Connection::open(&address, "test", Settings::minecraft()).await.unwrap();
+	assert_eq!(
+		c.kick(&steve, "spamming chat").await.unwrap(),
+		ModerationOutcome::Confirmed("done: kick Steve spamming chat".to_string())
+	);
+	let herobrine = PlayerRef::Name("Herobrine".to_string());
+	assert!(matches!(
+		c.kick(&herobrine, "").await.unwrap(),
+		ModerationOutcome::PlayerNotFound(_)
+	));
+	assert!(matches!(
+		c.ban(&steve, Some(Duration::from_secs(60)), "").await,
+		Err(Error::Unsupported(_))
+	));
+	assert!(matches!(c.kick(&steam, "").await, Err(Error::Unsupported(_))));
+	assert!(matches!(
+		c.kick(&steve, "a\nsay hi").await,
+		Err(Error::CommandContainsNewline)
+	));
+	// Names can't be quoted, so one that would run another command or spill into the reason is refused
+	for name in ["x\nop attacker", "Steve Jobs", "", "\0", "a_name_of_17_char"] {
+		let player = PlayerRef::Name(name.to_string());
+		assert!(
+			matches!(c.kick(&player, "").await, Err(Error::InvalidId(_))),
+			"{:?}",
+			name
+		);
+		assert!(
+			matches!(c.ban(&player, None, "").await, Err(Error::InvalidId(_))),
+			"{:?}",
+			name
+		);
+	}
+
+	let mut c = ReConnection::open(&address, "test", Settings::source()).await.unwrap();
+	assert_eq!(
+		c.kick(&steam, "be nice").await.unwrap(),
+		ModerationOutcome::Confirmed("done: kickid STEAM_1:0:11101 \"be nice\"".to_string())
+	);
+	assert_eq!(
+		c.ban(&steam, Some(Duration::from_secs(90)), "").await.unwrap(),
+		ModerationOutcome::Confirmed("done: banid 2 STEAM_1:0:11101 kick".to_string())
+	);
+	assert_eq!(
+		c.ban(&steam, None, "").await.unwrap(),
+		ModerationOutcome::Confirmed("done: banid 0 STEAM_1:0:11101 kick".to_string())
+	);
+	assert!(matches!(c.ban(&steve, None, "").await, Err(Error::Unsupported(_))));
+	c.close().await;
+
+	let c = Connection::open(&address, "test", Settings::ark()).await.unwrap();
+	assert!(matches!(
+		c.kick(&steam, "").await.unwrap(),
+		ModerationOutcome::PlayerNotFound(_)
+	));
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	assert!(matches!(c.kick(&steam, "").await, Err(Error::Unsupported(_))));
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn localization() {
+	let address = mock_server(|cmd| match cmd {
+		"kick Herobrine" => Some("Es wurde kein Spieler gefunden".to_string()),
+		"kick Notch" => Some("Speler niet gevonden".to_string()),
+		"status" => Some("Servername: Testserver\nmap: de_dust2\n".to_string()),
+		_ => Some(String::new()),
+	})
+	.await;
+	let herobrine = PlayerRef::Name("Herobrine".to_string());
+	let notch = PlayerRef::Name("Notch".to_string());
+
+	let c = Connection::open(&address, "test", Settings::minecraft()).await.unwrap();
+	assert!(matches!(
+		c.kick(&herobrine, "").await.unwrap(),
+		ModerationOutcome::PlayerNotFound(_)
+	));
+	assert!(matches!(
+		c.kick(&notch, "").await.unwrap(),
+		ModerationOutcome::Confirmed(_)
+	));
+
+	let settings = Settings {
+		localization: Localization::default().player_not_found("SPELER NIET GEVONDEN"),
+		..Settings::minecraft()
+	};
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	assert!(matches!(
+		c.kick(&notch, "").await.unwrap(),
+		ModerationOutcome::PlayerNotFound(_)
+	));
+	c.close().await;
+
+	let c = Connection::open(&address, "test", Settings::source()).await.unwrap();
+	assert_eq!(c.server_info().await.unwrap().name, None);
+	let settings = Settings::builder()
+		.profile(Profile::Source)
+		.localization(Localization::default().key("Servername", "hostname"))
+		.build()
+		.unwrap();
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	let info = c.server_info().await.unwrap();
+	assert_eq!(info.name, Some("Testserver".to_string()));
+	assert_eq!(info.map, Some("de_dust2".to_string()));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_hook() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let outcomes = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let recorded = outcomes.clone();
+	let hook = ExecHook::new(
+		|cmd| cmd.split(' ').next().unwrap_or_default().to_string(),
+		move |category, _, outcome| recorded.lock().unwrap().push((category.to_string(), outcome.is_ok())),
+	);
+	let settings = Settings {
+		exec_hook: Some(hook),
+		newline_mode: NewlineMode::Reject,
+		..Settings::default()
+	};
+	let c = Connection::open(address, "test", settings).await.unwrap();
+	c.exec("say hello").await.unwrap();
+	c.exec("save\nnow").await.unwrap_err();
+	assert_eq!(
+		*outcomes.lock().unwrap(),
+		vec![("say".to_string(), true), ("save\nnow".to_string(), false)]
+	);
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn transcript() {
+	let address = mock_server(|cmd| Some(format!("\"{}\"\n", cmd))).await;
+	let transcript = Transcript::new();
+	let settings = Settings {
+		transcript: Some(transcript.clone()),
+		newline_mode: NewlineMode::Reject,
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	c.exec("say hi").await.unwrap();
+	c.exec("a\nb").await.unwrap_err();
+
+	let entries = transcript.entries();
+	assert_eq!(entries.len(), 2);
+	assert_eq!(entries[0].response, Ok("\"say hi\"\n".to_string()));
+	assert!(entries[1].response.is_err());
+
+	let mut jsonl = Vec::new();
+	transcript.write_jsonl(&mut jsonl).unwrap();
+	let jsonl = String::from_utf8(jsonl).unwrap();
+	let first = jsonl.lines().next().unwrap();
+	assert!(first.ends_with(",\"command\":\"say hi\",\"response\":\"\\\"say hi\\\"\\n\"}"));
+
+	// A transcript with a capacity only keeps the most recent entries
+	let transcript = Transcript::with_capacity(2);
+	let settings = Settings {
+		transcript: Some(transcript.clone()),
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	for cmd in ["a", "b", "c"] {
+		c.exec(cmd).await.unwrap();
+	}
+	let commands: Vec<_> = transcript.entries().into_iter().map(|e| e.command).collect();
+	assert_eq!(commands, vec!["b", "c"]);
+
+	// A file transcript writes every entry as it is recorded, keeping none in memory
+	let path = std::env::temp_dir().join(format!("rercon-transcript-{}.jsonl", std::process::id()));
+	let transcript = Transcript::with_file(&path);
+	let settings = Settings {
+		transcript: Some(transcript.clone()),
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	c.exec("a").await.unwrap();
+	c.exec("b").await.unwrap();
+	let written = std::fs::read_to_string(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(written.lines().count(), 2);
+	assert!(transcript.entries().is_empty());
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn dry_run() {
+	let sent = Arc::new(AtomicUsize::new(0));
+	let counter = sent.clone();
+	let address = mock_server(move |cmd| {
+		if !cmd.is_empty() {
+			counter.fetch_add(1, Ordering::Relaxed);
+		}
+		Some(cmd.to_string())
+	})
+	.await;
+	let transcript = Transcript::new();
+	let settings = Settings {
+		dry_run: true,
+		transcript: Some(transcript.clone()),
+		..Settings::default()
+	}
+	.verify_command("version", "1.0");
+	let c = Connection::open(&address, "test", settings.clone()).await.unwrap();
+	assert_eq!(c.exec("stop").await.unwrap(), "");
+	assert_eq!(c.exec_no_response("stop", None).await.unwrap(), "");
+	assert!(c.exec_raw("stop").await.unwrap().is_empty());
+	assert!(matches!(c.exec("x".repeat(2000)).await, Err(Error::CommandTooLong)));
+	assert_eq!(transcript.entries().len(), 4);
+
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("stop").await.unwrap(), "");
+	c.close().await;
+	assert_eq!(sent.load(Ordering::Relaxed), 0);
+
+	assert!(Connection::open(
+		&address,
+		"wrong",
+		Settings {
+			dry_run: true,
+			..Settings::default()
+		}
+	)
+	.await
+	.is_err());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn into_stream() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+
+	let mut stream = c.into_stream().await.unwrap();
+	Packet::new(10, TYPE_EXEC, "raw")
+		.send_internal(Pin::new(&mut stream))
+		.await
+		.unwrap();
+	let reply = Packet::read(Pin::new(&mut stream), MAX_READ_PACKET_SIZE).await.unwrap();
+	assert_eq!(reply.get_id(), 10);
+	assert_eq!(reply.get_body(), b"raw");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn open_with_stream() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let stream = TcpStream::connect(&address).await.unwrap();
+	let c = Connection::open_with_stream(stream, "test", Settings::default())
+		.await
+		.unwrap();
+	assert_eq!(c.peer_addr().to_string(), address);
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+
+	let stream = TcpStream::connect(&address).await.unwrap();
+	let result = Connection::open_with_stream(stream, "wrong", Settings::default()).await;
+	assert!(matches!(result, Err(Error::PasswordIncorrect(_))));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_custom_terminator() {
+	// This server ignores empty commands entirely, so only a real terminator command can end the response.
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body)],
+		})
+	})
+	.await;
+
+	let settings = Settings {
+		terminator: "echo #end".to_string(),
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_interrupted() {
+	// This server silently drops the "ignored" command.
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"ignored") => vec![],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body)],
+		})
+	})
+	.await;
+
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let limit = Duration::from_millis(50);
+	assert!(matches!(c.exec_with_timeout("ignored", limit).await, Err(Error::Timeout(l)) if l == limit));
+	assert_eq!(c.exec_with_timeout("hello", limit).await.unwrap(), "hello");
+
+	let cancel = tokio::time::sleep(limit);
+	assert!(matches!(
+		c.exec_cancellable("ignored", cancel).await,
+		Err(Error::Cancelled)
+	));
+	assert_eq!(
+		c.exec_cancellable("hello", std::future::pending()).await.unwrap(),
+		"hello"
+	);
+
+	let settings = Settings {
+		response_timeout: Some(limit),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	assert!(matches!(c.exec("ignored").await, Err(Error::Timeout(l)) if l == limit));
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_no_response() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let grace = Some(Duration::from_millis(50));
+	assert_eq!(c.exec_no_response("error", grace).await.unwrap(), "error");
+	assert_eq!(c.exec_no_response("late", None).await.unwrap(), "");
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	assert_eq!(c.stats().unsolicited_packets, 1);
+	// Without an unsolicited buffer, the late response has nowhere to go
+	assert_eq!(c.stats().discarded_packets, 1);
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.exec_no_response("error", grace).await.unwrap(), "error");
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_raw() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let packets = c.exec_raw("hello").await.unwrap();
+	assert_eq!(packets.len(), 1);
+	assert_eq!(packets[0].get_packet_type(), TYPE_RESPONSE);
+	assert_eq!(packets[0].get_body(), b"hello");
+	let next = c.exec_raw("again").await.unwrap();
+	assert!(next[0].get_id() > packets[0].get_id());
+
+	let settings = Settings {
+		newline_mode: NewlineMode::Reject,
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	assert!(matches!(c.exec_raw("a\nb").await, Err(Error::CommandContainsNewline)));
+	assert_eq!(c.exec_raw("hello").await.unwrap()[0].clone().into_body(), b"hello");
+	c.close().await;
+}
+
+#[cfg(all(feature = "stream", feature = "reconnection"))]
+#[tokio::test]
+async fn exec_stream() {
+	use futures_core::Stream;
+
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"long") => vec![
+				Packet::new(id, TYPE_RESPONSE, "a"),
+				Packet::new(id, TYPE_RESPONSE, b"b\xc3".to_vec()),
+				Packet::new(id, TYPE_RESPONSE, b"\xa9c".to_vec()),
+			],
+			(_, b"bad") => vec![
+				Packet::new(id, TYPE_RESPONSE, "ok"),
+				Packet::new(id, TYPE_RESPONSE, b"\xff".to_vec()),
+			],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body.to_vec())],
+		})
+	})
+	.await;
+
+	async fn collect(mut stream: impl Stream<Item = Result<String, Error>> + Unpin) -> Vec<Result<String, Error>> {
+		let mut items = Vec::new();
+		while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+			items.push(item);
+		}
+		items
+	}
+
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let chunks = collect(c.exec_stream("long")).await;
+	assert_eq!(
+		chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+		vec!["a", "b", "éc"]
+	);
+	let chunks = collect(c.exec_stream("bad")).await;
+	assert_eq!(chunks.len(), 2);
+	assert_eq!(chunks[0].as_deref().unwrap(), "ok");
+	assert!(matches!(chunks[1], Err(Error::UTFEncoding(_))));
+
+	let mut c = ReConnection::open(address.to_string(), "test", Settings::default())
+		.await
+		.unwrap();
+	let chunks = collect(c.exec_stream("long")).await;
+	assert_eq!(
+		chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+		vec!["a", "b", "éc"]
+	);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_all() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		newline_mode: NewlineMode::Reject,
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings.clone()).await.unwrap();
+	let results = c.exec_all(&["a", "b\nc", "d"], BatchPolicy::Continue).await;
+	assert_eq!(results.len(), 3);
+	assert_eq!(results[0].as_deref().unwrap(), "a");
+	assert!(matches!(results[1], Err(Error::CommandContainsNewline)));
+	assert_eq!(results[2].as_deref().unwrap(), "d");
+
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	let results = c.exec_all(&["a", "b\nc", "d"], BatchPolicy::StopOnError).await;
+	assert_eq!(results.len(), 2);
+	assert!(results[1].is_err());
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_pipelined() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_uppercase()),
+	})
+	.await;
+	let cmds: Vec<_> = (0..20).map(|i| format!("ban {}", i)).collect();
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let results = c.exec_pipelined(&cmds, 4).await;
+	let expected: Vec<_> = (0..20).map(|i| format!("BAN {}", i)).collect();
+	assert_eq!(results.into_iter().collect::<Result<Vec<_>, _>>().unwrap(), expected);
+	assert!(c.exec_pipelined(&[] as &[&str], 0).await.is_empty());
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.exec_pipelined(&["a", "b"], 0).await.len(), 2);
+	let results = c.exec_pipelined(&["drop"], 2).await;
+	assert!(matches!(results[0], Err(Error::BusyReconnecting(_))));
+	c.close().await;
+}
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn diff_poller() {
+	let polls = Arc::new(AtomicUsize::new(0));
+	let address = mock_server(move |cmd| {
+		let chat = [
+			"",
+			"Steve: hi\n",
+			"Steve: hi\n",
+			"Steve: hi\nAlex: hi\nAlex: hi\n",
+			"Alex: hi\n",
+		];
+		match cmd {
+			"GetChat" => Some(chat[polls.fetch_add(1, Ordering::SeqCst).min(4)].to_string()),
+			cmd => Some(cmd.to_string()),
+		}
+	})
+	.await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let mut poller = DiffPoller::new(&c, "GetChat", Duration::from_secs(5));
+	assert_eq!(poller.command(), "GetChat");
+
+	// The empty first response and the unchanged third response are skipped
+	let start = Instant::now();
+	let changes = poller.next().await.unwrap();
+	assert_eq!(changes.added, vec!["Steve: hi"]);
+	assert_eq!(start.elapsed(), Duration::from_secs(5));
+	let changes = poller.next().await.unwrap();
+	assert_eq!(changes.added, vec!["Alex: hi", "Alex: hi"]);
+	assert_eq!(start.elapsed(), Duration::from_secs(15));
+	assert_eq!(
+		poller.next().await.unwrap(),
+		LineChanges {
+			added: vec![],
+			removed: vec!["Steve: hi".to_string(), "Alex: hi".to_string()],
+		}
+	);
+	assert_eq!(poller.lines().collect::<Vec<_>>(), vec!["Alex: hi"]);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn rcon_connection() {
+	async fn greet(connection: &mut impl RconConnection) -> String {
+		connection.exec("say hello").await.unwrap()
+	}
+
+	struct Mock(Vec<String>);
+	impl RconConnection for Mock {
+		fn exec<'a>(&'a mut self, cmd: &'a str) -> BoxFuture<'a, Result<String, Error>> {
+			self.0.push(cmd.to_string());
+			Box::pin(async { Ok("mocked".to_string()) })
+		}
+
+		fn close(self) -> BoxFuture<'static, ()> {
+			Box::pin(async {})
+		}
+	}
+
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let mut c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(greet(&mut c).await, "say hello");
+	RconConnection::close(c).await;
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(greet(&mut c).await, "say hello");
+	RconConnection::close(c).await;
+
+	let mut mock = Mock(Vec::new());
+	assert_eq!(greet(&mut mock).await, "mocked");
+	assert_eq!(mock.0, vec!["say hello"]);
+	let boxed: &mut dyn RconConnection = &mut mock;
+	assert_eq!(boxed.exec("save").await.unwrap(), "mocked");
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn settings_builder() {
+	let settings = SettingsBuilder::from(Settings::minecraft())
+		.response_timeout(Duration::from_secs(5))
+		.terminator("list")
+		.label("lobby")
+		.verify_command("seed", "Seed")
+		.build()
+		.unwrap();
+	assert_eq!(settings.profile, Profile::Minecraft);
+	assert_eq!(settings.response_timeout, Some(Duration::from_secs(5)));
+	assert_eq!(settings.terminator, "list");
+	assert_eq!(settings.label.as_deref(), Some("lobby"));
+	assert_eq!(settings.verification, Some(("seed".to_string(), "Seed".to_string())));
+	assert!(Settings::builder().build().is_ok());
+
+	let invalid = [
+		Settings::builder().connect_timeout(Duration::from_secs(0)),
+		Settings::builder().response_timeout(Duration::from_secs(0)),
+		Settings::builder().memory_limit(0),
+		Settings::builder().auth_failure_backoff(Duration::MAX),
+		Settings::builder().warm_standby(true).single_session(true),
+		Settings::builder().throttle_retries(3),
+	];
+	for builder in invalid {
+		assert!(matches!(builder.build(), Err(Error::InvalidSettings(_))));
+	}
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn ping() {
+	let execs = Arc::new(AtomicUsize::new(0));
+	let counter = execs.clone();
+	let address = mock_server(move |cmd| {
+		counter.fetch_add(1, Ordering::SeqCst);
+		match cmd {
+			"drop" => None,
+			cmd => Some(cmd.to_string()),
+		}
+	})
+	.await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let round_trip = c.ping().await.unwrap();
+	assert_eq!(c.round_trip_time(), Some(round_trip));
+	// The empty command and its terminator
+	assert_eq!(execs.load(Ordering::SeqCst), 2);
+	c.close().await;
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert!(c.ping().await.is_ok());
+	assert!(c.round_trip_time().await.is_some());
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert_eq!(c.round_trip_time().await, None);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn connection_metadata() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+	let before = Instant::now();
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.peer_addr().to_string(), address);
+	assert!(c.local_addr().ip().is_loopback());
+	assert_ne!(c.local_addr(), c.peer_addr());
+	assert!(c.connected_since() >= before && c.connected_since() <= Instant::now());
+	c.close().await;
+
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	assert_eq!(c.peer_addr().await.unwrap().to_string(), address);
+	assert!(c.local_addr().await.is_some());
+	assert!(c.connected_since().await.unwrap() >= before);
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert_eq!(c.peer_addr().await, None);
+	assert_eq!(c.connected_since().await, None);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn debug_redacts_password() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		reconnect_policy: Some(Arc::new(|_, _| ReconnectDecision::Retry)),
+		..Settings::source()
+	};
+	let debug = format!("{:?}", settings);
+	assert!(debug.contains("connect_timeout: 10s"));
+	assert!(debug.contains("reconnect_policy: Some(..)"));
+
+	let c = Connection::open(&address, "hunter2", settings.clone()).await.unwrap();
+	let debug = format!("{:?}", c);
+	assert!(debug.contains(&address));
+	assert!(debug.contains("in_flight: 0"));
+	assert!(!debug.contains("hunter2"));
+	c.close().await;
+
+	let c = ReConnection::open(&address, "hunter2", settings).await.unwrap();
+	let debug = format!("{:?}", c);
+	assert!(debug.contains("pass: <redacted>"));
+	assert!(debug.contains("status: Connected(Connection {"));
+	assert!(!debug.contains("hunter2"));
+	c.close().await;
 }
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn exec_at() {
+	// A server taking 100ms to respond to every command, reporting when each of them arrived
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let address = listener.local_addr().unwrap();
+	let (arrivals, mut arrived) = tokio::sync::mpsc::unbounded_channel();
+	tokio::spawn(async move {
+		let (mut stream, _) = listener.accept().await.unwrap();
+		while let Ok(packet) = Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE).await {
+			let reply = match packet.get_packet_type() {
+				TYPE_AUTH => Packet::new(packet.get_id(), TYPE_AUTH_RESPONSE, ""),
+				_ if packet.get_body().is_empty() => Packet::new(packet.get_id(), TYPE_RESPONSE, ""),
+				_ => {
+					arrivals.send(Instant::now()).unwrap();
+					tokio::time::sleep(Duration::from_millis(100)).await;
+					Packet::new(packet.get_id(), TYPE_RESPONSE, "done")
+				}
+			};
+			reply.send_internal(Pin::new(&mut stream)).await.unwrap();
+		}
+	});
+
+	let settings = Settings {
+		latency_compensation: true,
+		..Settings::default()
+	};
+	let c = Connection::open(address, "test", settings).await.unwrap();
+	assert_eq!(c.round_trip_time(), None);
+
+	// Nothing to compensate for before the first response
+	let at = Instant::now() + Duration::from_secs(1);
+	assert_eq!(c.exec_at("broadcast", at).await.unwrap(), "done");
+	let arrival = arrived.recv().await.unwrap();
+	assert!(arrival >= at && arrival < at + Duration::from_millis(10));
+	let round_trip = c.round_trip_time().unwrap();
+	assert!(round_trip >= Duration::from_millis(100) && round_trip < Duration::from_millis(110));
+
+	let at = Instant::now() + Duration::from_secs(1);
+	assert_eq!(c.exec_at("broadcast", at).await.unwrap(), "done");
+	let arrival = arrived.recv().await.unwrap();
+	assert!(arrival >= at - round_trip / 2 && arrival < at);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_detailed() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		response_filters: vec![ResponseFilter::trim_end()],
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings.clone()).await.unwrap();
+	let details = c.exec_detailed("hello \n").await.unwrap();
+	assert_eq!(details.response, "hello");
+	assert_eq!(details.packets, 1);
+	assert_eq!(details.end_id, Some(details.request_id + 1));
+
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	let first = c.exec_detailed("a").await.unwrap();
+	let second = c.exec_detailed("b").await.unwrap();
+	assert!(second.request_id > first.end_id.unwrap());
+	c.close().await;
+
+	let settings = Settings {
+		dry_run: true,
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	let details = c.exec_detailed("stop").await.unwrap();
+	assert_eq!((details.packets, details.end_id), (0, None));
+}
+
+#[cfg(feature = "broker")]
+#[tokio::test]
+async fn broker() {
+	let address = mock_server(|cmd| match cmd {
+		"long" => Some("é".repeat(500)),
+		"fail" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+	let settings = Settings {
+		newline_mode: NewlineMode::Split,
+		..Settings::default()
+	};
+	let handle = Connection::open(address, "test", settings).await.unwrap().into_handle();
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let broker = listener.local_addr().unwrap();
+	tokio::spawn(async move { handle.serve(listener, "local").await });
+
+	let a = Connection::open(broker, "local", Settings::default()).await.unwrap();
+	let b = Connection::open(broker, "local", Settings::default()).await.unwrap();
+	assert_eq!(a.exec("hello").await.unwrap(), "hello");
+	// Responses too long for a single packet are split without breaking up characters
+	assert_eq!(b.exec("long\nlong").await.unwrap(), "é".repeat(1000));
+	let details = b.exec_detailed("long\nlong").await.unwrap();
+	assert_eq!(details.packets, 2);
+	assert_eq!(b.exec("").await.unwrap(), "");
+
+	assert!(matches!(
+		Connection::open(broker, "test", Settings::default()).await,
+		Err(Error::PasswordIncorrect(_))
+	));
+	assert!(matches!(a.exec("fail").await, Err(Error::IO(_))));
+}
+
+#[cfg(feature = "broker")]
+#[tokio::test]
+async fn broker_malformed_packet() {
+	use tokio::io::AsyncWriteExt;
+
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let handle = Connection::open(address, "test", Settings::default())
+		.await
+		.unwrap()
+		.into_handle();
+	for len in [-1, 4, MAX_PACKET_SIZE as i32 + 1] {
+		let (mut client, server) = tokio::io::duplex(64);
+		client.write_all(&len.to_le_bytes()).await.unwrap();
+		let result = handle.serve_client(server, "local").await;
+		assert!(
+			matches!(result, Err(Error::IO(e)) if e.kind() == ErrorKind::InvalidData),
+			"{}",
+			len
+		);
+	}
+	assert_eq!(handle.exec("hello").await.unwrap(), "hello");
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn exec_to_writer() {
+	let address = mock_server(|cmd| Some(cmd.repeat(3))).await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let mut sink = Vec::new();
+	assert_eq!(c.exec_to_writer("ab", &mut sink).await.unwrap(), 6);
+	assert_eq!(sink, b"ababab");
+
+	// A failing writer is not a connection failure
+	let mut c = ReConnection::open(&address, "test", Settings::default()).await.unwrap();
+	let (writer, reader) = tokio::io::duplex(16);
+	drop(reader);
+	assert!(matches!(c.exec_to_writer("ab", writer).await, Err(Error::IO(_))));
+	assert!(matches!(c.status().await, ConnectionStatus::Connected));
+	assert_eq!(c.exec("hello").await.unwrap(), "hellohellohello");
+	c.close().await;
+}
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn auth_timeout() {
+	// A server that never answers the auth packet
+	let address = scripted_server(|_| Some(Vec::new())).await;
+
+	match Connection::open(&address, "test", Settings::default()).await {
+		Err(Error::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::TimedOut),
+		Err(e) => panic!("expected a timeout, got {:?}", e),
+		Ok(_) => panic!("expected a timeout"),
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn connect_budget() {
+	// A listener that never accepts, so connecting hangs once its backlog is full
+	let socket = tokio::net::TcpSocket::new_v4().unwrap();
+	socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+	let address = socket.local_addr().unwrap();
+	let _listener = socket.listen(1).unwrap();
+	let mut backlog = Vec::new();
+	while let Ok(stream) = tokio::time::timeout(Duration::from_secs(1), TcpStream::connect(address)).await {
+		backlog.push(stream.unwrap());
+	}
+
+	// Each of these addresses would take the whole connect_timeout, the budget cuts that short
+	let settings = Settings {
+		connect_timeout: Duration::from_secs(10),
+		connect_budget: Some(Duration::from_secs(15)),
+		..Settings::default()
+	};
+	let start = Instant::now();
+	match Connection::open(&[address, address, address][..], "test", settings).await {
+		Err(Error::IO(e)) => assert_eq!(e.to_string(), "connect budget exceeded"),
+		Err(e) => panic!("expected the budget to run out, got {:?}", e),
+		Ok(_) => panic!("expected the budget to run out"),
+	}
+	assert_eq!(start.elapsed(), Duration::from_secs(15));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn auth_rejected_by_hanging_up() {
+	let address = scripted_server(|_| None).await;
+
+	let result = Connection::open(&address, "test", Settings::default()).await;
+	assert!(matches!(result, Err(Error::PasswordIncorrect(reason)) if reason.is_empty()));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn packet_pacing() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let settings = Settings {
+		packet_pacing: Some(Duration::from_secs(1)),
+		..Settings::default()
+	};
+	let c = Connection::open(address, "test", settings).await.unwrap();
+	let start = Instant::now();
+	// Two execs send four packets, so three delays are waited out.
+	c.exec("a").await.unwrap();
+	c.exec("b").await.unwrap();
+	assert!(start.elapsed() >= Duration::from_secs(3));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test(start_paused = true)]
+async fn throttle_retries() {
+	// This server throttles every other command.
+	let throttled = Arc::new(AtomicBool::new(false));
+	let address = mock_server(move |cmd| match cmd {
+		"" => Some(String::new()),
+		_ if !throttled.fetch_xor(true, Ordering::SeqCst) => Some("Too many commands, wait 5s".to_string()),
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+	let detector: ThrottleDetector = Arc::new(|response| {
+		let seconds = response.strip_prefix("Too many commands, wait ")?.strip_suffix('s')?;
+		Some(Duration::from_secs(seconds.parse().ok()?))
+	});
+	let settings = |throttle_retries| Settings {
+		throttle_detector: Some(detector.clone()),
+		throttle_retries,
+		..Settings::default()
+	};
+
+	let c = Connection::open(&address, "test", settings(1)).await.unwrap();
+	let start = Instant::now();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	assert!(start.elapsed() >= Duration::from_secs(5));
+
+	let c = Connection::open(&address, "test", settings(0)).await.unwrap();
+	assert!(matches!(
+		c.exec("hello").await,
+		Err(Error::Throttled { retry_after }) if retry_after == Duration::from_secs(5)
+	));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn empty_password() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(address, "", Settings::default()).await.unwrap();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+
+	// This server doesn't know about auth packets, and hangs up on them.
+	let address = scripted_server(|packet| match packet.get_packet_type() {
+		TYPE_EXEC => Some(vec![Packet::new(packet.get_id(), TYPE_RESPONSE, packet.get_body())]),
+		_ => None,
+	})
+	.await;
+
+	let settings = Settings {
+		skip_auth_without_password: true,
+		..Settings::default()
+	};
+	let c = Connection::open(address, "", settings).await.unwrap();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_source_fragment_marker() {
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![Packet::new(id, TYPE_RESPONSE, "")],
+			(_, _) => vec![
+				Packet::new(id, TYPE_RESPONSE, "hostname: test\n"),
+				Packet::new(id, TYPE_RESPONSE, vec![0x00, 0x01]),
+				Packet::new(id, TYPE_RESPONSE, "map: de_dust2"),
+			],
+		})
+	})
+	.await;
+
+	let c = Connection::open(&address, "test", Settings::source()).await.unwrap();
+	assert_eq!(c.exec("status").await.unwrap(), "hostname: test\nmap: de_dust2");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_source_trailing_marker() {
+	// The marker follows the mirrored terminator, after the command has already finished
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![
+				Packet::new(id, TYPE_RESPONSE, ""),
+				Packet::new(id, TYPE_RESPONSE, vec![0x00, 0x01]),
+			],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body)],
+		})
+	})
+	.await;
+
+	let settings = Settings {
+		unsolicited: Some(UnsolicitedBuffer {
+			capacity: 10,
+			overflow: OverflowPolicy::DropOldest,
+		}),
+		..Settings::source()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("first").await.unwrap(), "first");
+	assert_eq!(c.exec("second").await.unwrap(), "second");
+	assert_eq!(c.stats().unsolicited_packets, 0);
+	assert!(c.drain_unsolicited().unwrap().is_empty());
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn self_test() {
+	// A server with a long cvarlist, which never answers "ignored"
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"ignored") => vec![],
+			(_, b"cvarlist") => vec![
+				Packet::new(id, TYPE_RESPONSE, "sv_cheats : 0\n"),
+				Packet::new(id, TYPE_RESPONSE, "sv_gravity : 800\n"),
+			],
+			(_, body) => vec![Packet::new(
+				id,
+				TYPE_RESPONSE,
+				body.strip_prefix(b"echo ").unwrap_or(body).to_vec(),
+			)],
+		})
+	})
+	.await;
+
+	let c = Connection::open(&address, "test", Settings::source()).await.unwrap();
+	let report = c.self_test().await.unwrap();
+	assert_eq!(
+		report,
+		SelfTestReport {
+			terminator: SelfTestCheck::Passed,
+			multi_packet: SelfTestCheck::Passed,
+			encoding: SelfTestCheck::Passed,
+		}
+	);
+	assert!(report.passed());
+
+	let settings = Settings {
+		terminator: "ignored".to_string(),
+		response_timeout: Some(Duration::from_millis(50)),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address.to_string(), "test", settings).await.unwrap();
+	let report = c.self_test().await.unwrap();
+	assert!(matches!(report.terminator, SelfTestCheck::Failed(_)));
+	assert!(matches!(report.multi_packet, SelfTestCheck::Inconclusive(_)));
+	assert!(matches!(report.encoding, SelfTestCheck::Inconclusive(_)));
+	assert!(!report.passed());
+	c.close().await;
+
+	// A help that fits in a single packet tells nothing about reassembly
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(&address, "test", Settings::minecraft()).await.unwrap();
+	let report = c.self_test().await.unwrap();
+	assert_eq!(report.terminator, SelfTestCheck::Passed);
+	assert!(matches!(report.multi_packet, SelfTestCheck::Inconclusive(_)));
+	assert!(report.passed());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn unsolicited_buffer() {
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![Packet::new(id, TYPE_RESPONSE, "")],
+			(_, body) => vec![
+				Packet::new(0, TYPE_RESPONSE, ""),
+				Packet::new(0, TYPE_RESPONSE, "chat 1"),
+				Packet::new(0, TYPE_RESPONSE, "chat 2"),
+				Packet::new(0, TYPE_RESPONSE, "chat 3"),
+				Packet::new(id, TYPE_RESPONSE, body),
+			],
+		})
+	})
+	.await;
+
+	let settings = |overflow| Settings {
+		unsolicited: Some(UnsolicitedBuffer { capacity: 2, overflow }),
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings(OverflowPolicy::DropOldest))
+		.await
+		.unwrap();
+	assert_eq!(c.exec("hi").await.unwrap(), "hi");
+	assert_eq!(c.drain_unsolicited().unwrap(), vec!["chat 2", "chat 3"]);
+	assert!(c.drain_unsolicited().unwrap().is_empty());
+	assert_eq!(
+		c.stats(),
+		Stats {
+			keepalive_frames: 1,
+			unsolicited_packets: 3,
+			discarded_packets: 1,
+		}
+	);
+
+	let c = Connection::open(&address, "test", settings(OverflowPolicy::Error))
+		.await
+		.unwrap();
+	assert_eq!(c.exec("hi").await.unwrap(), "hi");
+	assert!(matches!(c.drain_unsolicited(), Err(Error::UnsolicitedOverflow(1))));
+	assert_eq!(c.drain_unsolicited().unwrap(), vec!["chat 1", "chat 2"]);
+
+	// A split connection hands out the messages along with the responses
+	let c = Connection::open(&address, "test", settings(OverflowPolicy::DropOldest))
+		.await
+		.unwrap();
+	let (sender, mut receiver) = c.split();
+	assert_eq!(sender.send("hi"), 0);
+	let mut messages = Vec::new();
+	while messages.len() < 2 {
+		match receiver.recv().await.unwrap() {
+			Incoming::Unsolicited(message) => messages.push(message.unwrap()),
+			Incoming::Response(response) => {
+				assert_eq!((response.ticket, response.command.as_str()), (0, "hi"));
+				assert_eq!(response.result.unwrap(), "hi");
+			}
+		}
+	}
+	assert!(messages.len() == 2 && messages.iter().all(|message| message.starts_with("chat")));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn split() {
+	let address = mock_server(|cmd| Some(cmd.to_string())).await;
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	let (sender, mut receiver) = c.split();
+	let other = sender.clone();
+	let tickets = [sender.send("a"), other.send("b"), sender.send("c")];
+	assert_eq!(tickets, [0, 1, 2]);
+	drop((sender, other));
+
+	let mut responses = Vec::new();
+	while let Some(incoming) = receiver.recv().await {
+		match incoming {
+			Incoming::Response(response) => responses.push((response.ticket, response.result.unwrap())),
+			Incoming::Unsolicited(message) => panic!("unexpected message {:?}", message),
+		}
+	}
+	responses.sort();
+	assert_eq!(
+		responses,
+		vec![(0, "a".to_string()), (1, "b".to_string()), (2, "c".to_string())]
+	);
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_response_limits() {
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![Packet::new(id, TYPE_RESPONSE, "")],
+			(_, _) => vec![
+				Packet::new(id, TYPE_RESPONSE, "line 1\n"),
+				Packet::new(id, TYPE_RESPONSE, "line 2 \u{e9}\n"),
+				Packet::new(id, TYPE_RESPONSE, "line 3\n"),
+			],
+		})
+	})
+	.await;
+
+	let c = Connection::open(&address, "test", Settings::default()).await.unwrap();
+	// The cutoff falls within the two byte long é, which is dropped entirely.
+	let head = c.exec_with_cutoff("GetGameLog", 15).await.unwrap();
+	assert_eq!(head.response, "line 1\nline 2 ");
+	assert!(head.truncated);
+
+	let all = c.exec_with_cutoff("GetGameLog", 1000).await.unwrap();
+	assert_eq!(all.response, "line 1\nline 2 \u{e9}\nline 3\n");
+	assert!(!all.truncated);
+
+	let settings = Settings {
+		memory_limit: Some(20),
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	assert!(matches!(
+		c.exec("GetGameLog").await,
+		Err(Error::MemoryLimitExceeded(20))
+	));
+	// The rest of the response was still consumed, so the connection remains usable.
+	assert_eq!(c.exec_with_cutoff("GetGameLog", 7).await.unwrap().response, "line 1\n");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn exec_dedup_responses() {
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![Packet::new(id, TYPE_RESPONSE, "")],
+			(_, body) => vec![
+				Packet::new(id, TYPE_RESPONSE, body),
+				Packet::new(id, TYPE_RESPONSE, body),
+			],
+		})
+	})
+	.await;
+
+	let settings = Settings {
+		dedup_responses: true,
+		..Settings::default()
+	};
+	let c = Connection::open(&address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("once").await.unwrap(), "once");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn probe_server() {
+	let address = mock_server(|_| Some("pong".to_string())).await;
+	assert!(probe(&address, "test", Settings::default()).await.is_ok());
+	assert!(matches!(
+		probe(&address, "wrong", Settings::default()).await,
+		Err(Error::PasswordIncorrect(_))
+	));
+	assert!(check_auth(&address, "test", Settings::default()).await.is_ok());
+	assert!(matches!(
+		check_auth(&address, "wrong", Settings::default()).await,
+		Err(Error::PasswordIncorrect(_))
+	));
+
+	// A server that accepts but never answers must not hang the probe.
+	let address = scripted_server(|_| Some(Vec::new())).await;
+	let settings = Settings {
+		connect_timeout: Duration::from_millis(100),
+		..Settings::default()
+	};
+	match probe(&address, "test", settings).await {
+		Err(Error::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::TimedOut),
+		other => panic!("expected timeout, got {:?}", other),
+	}
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn probe_custom_terminator() {
+	// This server ignores empty commands, so only a real terminator command gets an answer.
+	let address = scripted_server(|packet| {
+		let id = packet.get_id();
+		Some(match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) => vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")],
+			(_, b"") => vec![],
+			(_, body) => vec![Packet::new(id, TYPE_RESPONSE, body)],
+		})
+	})
+	.await;
+
+	// Pinging with an empty command would run into the deadline instead
+	let settings = Settings {
+		terminator: "echo".to_string(),
+		connect_timeout: Duration::from_secs(1),
+		..Settings::default()
+	};
+	let report = probe(&address, "test", settings).await.unwrap();
+	assert!(report.round_trip < Duration::from_secs(1));
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn reconnect_detects_restart() {
+	let boot = Arc::new(AtomicUsize::new(0));
+	let server_boot = boot.clone();
+	let address = mock_server(move |cmd| match cmd {
+		"boot" => Some(server_boot.load(Ordering::SeqCst).to_string()),
+		"drop" => None,
+		"restart" => {
+			server_boot.fetch_add(1, Ordering::SeqCst);
+			None
+		}
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let settings = Settings {
+		restart_probe: Some("boot".to_string()),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+
+	for (cmd, expected) in [("drop", Event::Reconnected), ("restart", Event::ServerRestarted)] {
+		assert!(matches!(c.exec(cmd).await, Err(Error::BusyReconnecting(_))));
+		assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+		assert_eq!(events.recv().await.unwrap(), expected);
+	}
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_reports_failed_attempts() {
+	let probes = Arc::new(AtomicUsize::new(0));
+	let address = mock_server(move |cmd| match cmd {
+		"drop" => None,
+		// Drop the connection during the first reconnect attempt.
+		"boot" if probes.fetch_add(1, Ordering::SeqCst) == 1 => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let settings = Settings {
+		restart_probe: Some("boot".to_string()),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert!(matches!(
+		events.recv().await.unwrap(),
+		Event::ReconnectFailed { code: "io", .. }
+	));
+	assert_eq!(events.recv().await.unwrap(), Event::Reconnected);
+
+	let history = c.reconnect_history().await;
+	assert_eq!(history.len(), 1);
+	assert_eq!(history[0].code, "io");
+	assert_eq!(history[0].attempts, 2);
+	assert!(!history[0].gave_up);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_auth_failure_backoff() {
+	// A server that rejects the password while `reject` is set
+	let reject = Arc::new(AtomicBool::new(false));
+	let server_reject = reject.clone();
+	let address = scripted_server(move |packet| {
+		let id = packet.get_id();
+		match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) if server_reject.load(Ordering::SeqCst) => {
+				Some(vec![Packet::new(-1, TYPE_AUTH_RESPONSE, "")])
+			}
+			(TYPE_AUTH, _) => Some(vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")]),
+			(_, b"drop") => None,
+			(_, body) => Some(vec![Packet::new(id, TYPE_RESPONSE, body)]),
+		}
+	})
+	.await;
+
+	// Returns the delay scheduled after the next failed attempt
+	async fn next_backoff(c: &ReConnection, events: &mut tokio::sync::broadcast::Receiver<Event>) -> Duration {
+		loop {
+			if let Event::ReconnectFailed { code, .. } = events.recv().await.unwrap() {
+				assert_eq!(code, "password_incorrect");
+				break;
+			}
+		}
+		loop {
+			if let ConnectionStatus::Reconnecting {
+				next_attempt_in: Some(delay),
+				..
+			} = c.status().await
+			{
+				return delay;
+			}
+			tokio::task::yield_now().await;
+		}
+	}
+
+	let settings = Settings {
+		auth_failure_backoff: Duration::from_secs(10),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	let mut events = c.events();
+	reject.store(true, Ordering::SeqCst);
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	for backoff in [10, 20, 40, 80, 160, 320, 320] {
+		assert_eq!(next_backoff(&c, &mut events).await, Duration::from_secs(backoff));
+	}
+	c.close().await;
+
+	// The doubled delay never exceeds a day, even with a backoff too long to pass validation
+	let hour = Duration::from_secs(60 * 60);
+	for (backoff, expected) in [(hour * 3, vec![3, 6, 12, 24, 24]), (Duration::MAX, vec![24, 24])] {
+		reject.store(false, Ordering::SeqCst);
+		let settings = Settings {
+			auth_failure_backoff: backoff,
+			..Settings::default()
+		};
+		let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+		let mut events = c.events();
+		reject.store(true, Ordering::SeqCst);
+		assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+		for hours in expected {
+			assert_eq!(next_backoff(&c, &mut events).await, hour * hours);
+		}
+		c.close().await;
+	}
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_jitter_per_attempt() {
+	// A server that rejects the password while `reject` is set
+	let reject = Arc::new(AtomicBool::new(false));
+	let server_reject = reject.clone();
+	let address = scripted_server(move |packet| {
+		let id = packet.get_id();
+		match (packet.get_packet_type(), packet.get_body()) {
+			(TYPE_AUTH, _) if server_reject.load(Ordering::SeqCst) => {
+				Some(vec![Packet::new(-1, TYPE_AUTH_RESPONSE, "")])
+			}
+			(TYPE_AUTH, _) => Some(vec![Packet::new(id, TYPE_AUTH_RESPONSE, "")]),
+			(_, b"drop") => None,
+			(_, body) => Some(vec![Packet::new(id, TYPE_RESPONSE, body)]),
+		}
+	})
+	.await;
+
+	// Without a backoff, the only delay scheduled after a failed attempt is the jitter before the next one
+	let max = Duration::from_secs(100);
+	let settings = Settings {
+		auth_failure_backoff: Duration::ZERO,
+		reconnect_jitter: Some(max),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(&address, "test", settings).await.unwrap();
+	let mut events = c.events();
+	reject.store(true, Ordering::SeqCst);
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	let mut delays = Vec::new();
+	while delays.len() < 2 {
+		if let Event::ReconnectFailed { .. } = events.recv().await.unwrap() {
+			// Time stands still while polling, so the jitter stays scheduled until it is observed
+			loop {
+				if let ConnectionStatus::Reconnecting {
+					next_attempt_in: Some(delay),
+					..
+				} = c.status().await
+				{
+					if !delay.is_zero() {
+						delays.push(delay);
+						break;
+					}
+				}
+				tokio::task::yield_now().await;
+			}
+		}
+	}
+	assert!(delays.iter().all(|delay| *delay <= max));
+	assert_ne!(delays[0], delays[1]);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn reconnect_warm_standby() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let settings = Settings {
+		warm_standby: true,
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+	// Give the standby connection some time to be set up.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert_eq!(events.recv().await.unwrap(), Event::Reconnected);
+	assert_eq!(c.reconnect_history().await[0].attempts, 0);
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(flavor = "multi_thread")]
+async fn reconnect_status_during_reconnect() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	// The jitter delays the reconnect until the status is being read in a loop.
+	let settings = Settings {
+		reconnect_jitter: Some(Duration::from_millis(50)),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+
+	// Reading the status while the reconnect completes must not catch it halfway.
+	let c = Arc::new(c);
+	let readers: Vec<_> = (0..8)
+		.map(|_| {
+			let c = c.clone();
+			tokio::spawn(async move { while c.status().await != ConnectionStatus::Connected {} })
+		})
+		.collect();
+	for reader in readers {
+		tokio::time::timeout(Duration::from_secs(5), reader)
+			.await
+			.unwrap()
+			.unwrap();
+	}
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn reconnect_policy_gives_up() {
+	let checks = Arc::new(AtomicUsize::new(0));
+	let address = mock_server(move |cmd| match cmd {
+		"drop" => None,
+		// Pretend a different server took over the port after the first connection.
+		"check" if checks.fetch_add(1, Ordering::SeqCst) == 0 => Some("ok".to_string()),
+		"check" => Some("something else".to_string()),
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let policy: ReconnectPolicy = Arc::new(|kind, _| match kind {
+		FailureKind::Protocol => ReconnectDecision::GiveUp,
+		_ => ReconnectDecision::Retry,
+	});
+	let settings = Settings {
+		reconnect_policy: Some(policy),
+		..Settings::default()
+	}
+	.verify_command("check", "ok");
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert!(matches!(events.recv().await.unwrap(), Event::ReconnectFailed { .. }));
+	assert!(matches!(events.recv().await.unwrap(), Event::GaveUp(_)));
+	assert!(matches!(c.status().await, ConnectionStatus::GaveUp { .. }));
+	assert!(matches!(c.exec("hello").await, Err(Error::GaveUp(_))));
+	let history = c.reconnect_history().await;
+	assert_eq!((history.len(), history[0].attempts), (1, 1));
+	assert!(history[0].gave_up);
+	c.close().await;
+
+	let refused = match Connection::open("127.0.0.1:1", "test", Settings::default()).await {
+		Err(e) => e,
+		Ok(_) => panic!("nothing should be listening on port 1"),
+	};
+	assert_eq!(FailureKind::of(&refused), FailureKind::Refused);
+	assert_eq!(
+		FailureKind::of(&Error::PasswordIncorrect(String::new())),
+		FailureKind::Auth
+	);
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_policy_panics() {
+	let checks = Arc::new(AtomicUsize::new(0));
+	let address = mock_server(move |cmd| match cmd {
+		"drop" => None,
+		// Only the first reconnect attempt fails
+		"check" if checks.fetch_add(1, Ordering::SeqCst) == 1 => Some("something else".to_string()),
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let panicked = Arc::new(AtomicBool::new(false));
+	let settings = Settings {
+		reconnect_policy: Some(Arc::new(move |_, _| {
+			if !panicked.swap(true, Ordering::SeqCst) {
+				panic!("policy bug");
+			}
+			ReconnectDecision::Retry
+		})),
+		..Settings::default()
+	}
+	.verify_command("check", "check");
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert!(matches!(events.recv().await.unwrap(), Event::ReconnectFailed { .. }));
+	assert_eq!(
+		events.recv().await.unwrap(),
+		Event::InternalError("policy bug".to_string())
+	);
+	assert_eq!(events.recv().await.unwrap(), Event::Reconnected);
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	c.close().await;
+	assert_eq!(Error::InternalError(String::new()).code(), "internal_error");
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_maintenance() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let mut c = ReConnection::open(address, "test", Settings::default()).await.unwrap();
+	c.schedule_maintenance(Duration::from_secs(10), Duration::from_secs(60))
+		.await;
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	tokio::time::sleep(Duration::from_secs(10)).await;
+	assert!(matches!(c.exec("hello").await, Err(Error::InMaintenance(d)) if d == Duration::from_secs(60)));
+	c.end_maintenance().await;
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+
+	// Reconnect attempts wait for the window to end
+	let mut events = c.events();
+	let start = Instant::now();
+	assert!(matches!(c.exec("drop").await, Err(Error::BusyReconnecting(_))));
+	c.schedule_maintenance(Duration::ZERO, Duration::from_secs(30)).await;
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert_eq!(events.recv().await.unwrap(), Event::Reconnected);
+	assert!(start.elapsed() >= Duration::from_secs(30));
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn single_session() {
+	// A server that closes the previous connection once a new one authenticates
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let address = listener.local_addr().unwrap();
+	let (latest, _) = tokio::sync::watch::channel(0);
+	tokio::spawn(async move {
+		for session in 1.. {
+			let (mut stream, _) = listener.accept().await.unwrap();
+			let latest = latest.clone();
+			let mut superseded = latest.subscribe();
+			tokio::spawn(async move {
+				loop {
+					let packet = tokio::select! {
+						packet = Packet::read(Pin::new(&mut stream), MAX_PACKET_SIZE) => packet.unwrap(),
+						_ = superseded.wait_for(|latest| *latest > session) => break,
+					};
+					let reply = match packet.get_packet_type() {
+						TYPE_AUTH => {
+							latest.send_replace(session);
+							Packet::new(packet.get_id(), TYPE_AUTH_RESPONSE, "")
+						}
+						_ => Packet::new(packet.get_id(), TYPE_RESPONSE, packet.get_body().to_vec()),
+					};
+					reply.send_internal(Pin::new(&mut stream)).await.unwrap();
+				}
+			});
+		}
+	});
+
+	let settings = Settings {
+		single_session: true,
+		reconnect_policy: Some(Arc::new(|kind, _| match kind {
+			FailureKind::Superseded => ReconnectDecision::GiveUp,
+			_ => ReconnectDecision::Retry,
+		})),
+		..Settings::default()
+	};
+	let mut a = ReConnection::open(address.to_string(), "test", settings.clone())
+		.await
+		.unwrap();
+	let mut events = a.events();
+	let b = Connection::open(address, "test", settings.clone()).await.unwrap();
+	assert_eq!(b.exec("hello").await.unwrap(), "hello");
+	// Give the closed connection some time to be noticed
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(matches!(a.exec("hello").await, Err(Error::GaveUp(_))));
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert!(matches!(events.recv().await.unwrap(), Event::GaveUp(_)));
+	a.close().await;
+
+	let c = Connection::open(address, "test", settings).await.unwrap();
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(matches!(b.exec("hello").await, Err(Error::Superseded)));
+	assert_eq!(FailureKind::of(&Error::Superseded), FailureKind::Superseded);
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test]
+async fn reconnect_circuit_breaker() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let settings = Settings {
+		circuit_breaker: Some(CircuitBreaker {
+			failure_threshold: 1,
+			cooldown: Duration::from_secs(60),
+		}),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	assert!(matches!(c.exec("drop").await, Err(Error::CircuitOpen(_))));
+	match c.exec("hello").await {
+		Err(Error::CircuitOpen(remaining)) => assert!(remaining > Duration::from_secs(50)),
+		_ => panic!("expected the circuit breaker to be open"),
+	}
+	c.close().await;
+}
+
+#[cfg(feature = "reconnection")]
+#[tokio::test(start_paused = true)]
+async fn reconnect_circuit_breaker_half_open() {
+	let address = mock_server(|cmd| match cmd {
+		"drop" => None,
+		cmd => Some(cmd.to_string()),
+	})
+	.await;
+
+	let settings = Settings {
+		circuit_breaker: Some(CircuitBreaker {
+			failure_threshold: 1,
+			cooldown: Duration::from_secs(60),
+		}),
+		..Settings::default()
+	};
+	let mut c = ReConnection::open(address, "test", settings).await.unwrap();
+	let mut events = c.events();
+	let start = Instant::now();
+
+	// Time is paused, so the remaining cool-down is exact and the runtime skips ahead to the reconnect once it is idle.
+	assert!(matches!(c.exec("drop").await, Err(Error::CircuitOpen(d)) if d == Duration::from_secs(60)));
+	assert!(matches!(
+		c.status().await,
+		ConnectionStatus::Reconnecting { next_attempt_in: Some(d), .. } if d == Duration::from_secs(60)
+	));
+	assert!(matches!(events.recv().await.unwrap(), Event::Disconnected(_)));
+	assert_eq!(events.recv().await.unwrap(), Event::Reconnected);
+	assert!(start.elapsed() >= Duration::from_secs(60));
+	assert_eq!(c.status().await, ConnectionStatus::Connected);
+	assert_eq!(c.exec("hello").await.unwrap(), "hello");
+	c.close().await;
+}
+
+#[test]
+fn error_to_wire() {
+	let e = Error::from(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused"));
+	let wire = e.to_wire();
+	assert_eq!(wire.code, "io");
+	assert_eq!(wire.sources, vec!["refused".to_string()]);
+	assert_eq!(
+		Error::PasswordIncorrect(String::new()).to_wire().code,
+		"password_incorrect"
+	);
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn response_filters() {
+	let apply = |filter: ResponseFilter, s: &str| filter.apply(s.to_string());
+	assert_eq!(apply(ResponseFilter::trim_end(), "Hello \n\0\0"), "Hello");
+	assert_eq!(
+		apply(ResponseFilter::collapse_trailing_newlines(), "a\nb\n\n\r\n"),
+		"a\nb\n"
+	);
+	assert_eq!(apply(ResponseFilter::collapse_trailing_newlines(), "a"), "a");
+	assert_eq!(
+		apply(ResponseFilter::strip_minecraft_formatting(), "§aGreen§r text"),
+		"Green text"
+	);
+	assert_eq!(
+		apply(
+			ResponseFilter::strip_ark_no_response(),
+			"Server received, But no response!! \n "
+		),
+		""
+	);
+	// Ark: Survival Ascended varies the casing and punctuation
+	for placeholder in [
+		"Server received, but no response!\n",
+		"server received, but no response",
+	] {
+		assert_eq!(apply(ResponseFilter::strip_ark_no_response(), placeholder), "");
+	}
+	assert_eq!(
+		apply(
+			ResponseFilter::strip_ark_no_response(),
+			"Server received, But no response!! Or did it?"
+		),
+		"Server received, But no response!! Or did it?"
+	);
+}
+
+#[cfg(all(feature = "client", feature = "unicode"))]
+#[test]
+fn response_filters_unicode() {
+	let apply = |filter: ResponseFilter, s: &str| filter.apply(s.to_string());
+	// A combining diaeresis, and the "fi" ligature which only NFKC replaces
+	assert_eq!(apply(ResponseFilter::nfc(), "Zoe\u{308}"), "Zo\u{eb}");
+	assert_eq!(apply(ResponseFilter::nfc(), "\u{fb01}re"), "\u{fb01}re");
+	assert_eq!(apply(ResponseFilter::nfkc(), "\u{fb01}re"), "fire");
+}
+
+#[test]
+fn parse_combinators() {
+	use crate::parse::{comma_list, count_of, fixed_width_table, key_values, numbered_lines};
+
+	let pairs: Vec<_> = key_values("hostname: My: Server\nno separator\nmap : de_dust2", ':').collect();
+	assert_eq!(pairs, vec![("hostname", "My: Server"), ("map", "de_dust2")]);
+	assert_eq!(
+		comma_list(" Steve, Alex,\nNotch, ").collect::<Vec<_>>(),
+		vec!["Steve", "Alex", "Notch"]
+	);
+	assert_eq!(
+		numbered_lines("Players:\n0. Steve\n 1. Alex\n").collect::<Vec<_>>(),
+		vec![(0, "Steve"), (1, "Alex")]
+	);
+	assert_eq!(count_of("There are 2 of a max of 20 players online"), Some((2, 20)));
+	assert_eq!(count_of("(3/8)"), Some((3, 8)));
+	assert_eq!(count_of("none"), None);
+
+	let table = fixed_width_table("name    ping  state\nSteve   23    active\nZoë           afk\n\n");
+	assert_eq!(table.len(), 2);
+	assert_eq!(
+		(table[0]["name"], table[0]["ping"], table[0]["state"]),
+		("Steve", "23", "active")
+	);
+	assert_eq!(
+		(table[1]["name"], table[1]["ping"], table[1]["state"]),
+		("Zoë", "", "afk")
+	);
+}
+
+#[test]
+fn command_template() {
+	let template = CommandTemplate::new("kick {player} {{reason: {reason}}}").unwrap();
+	assert_eq!(template.placeholders().collect::<Vec<_>>(), vec!["player", "reason"]);
+	assert_eq!(
+		template
+			.render(&[("reason", r#"said "hi" \o/"#), ("player", "Steve")])
+			.unwrap(),
+		r#"kick Steve {reason: "said \"hi\" \\o/"}"#
+	);
+	assert_eq!(
+		template.render(&[("player", ""), ("reason", "x")]).unwrap(),
+		r#"kick "" {reason: x}"#
+	);
+
+	assert!(matches!(
+		template.render(&[("player", "Steve")]),
+		Err(Error::InvalidTemplate(_))
+	));
+	assert!(matches!(
+		template.render(&[("player", "Steve"), ("reason", "x"), ("extra", "y")]),
+		Err(Error::InvalidTemplate(_))
+	));
+	assert!(matches!(
+		template.render(&[("player", "Steve\nstop"), ("reason", "x")]),
+		Err(Error::InvalidTemplate(_))
+	));
+	for invalid in &["say {", "say }", "say {}", "say {a b}"] {
+		assert!(
+			matches!(CommandTemplate::new(invalid), Err(Error::InvalidTemplate(_))),
+			"{}",
+			invalid
+		);
+	}
+
+	let player = "Alex";
+	assert_eq!(crate::command!("kick {player}", player = player).unwrap(), "kick Alex");
+	assert_eq!(crate::command!("say {{{n}}}", n = 3).unwrap(), "say {3}");
+	assert!(crate::command!("say {message}", message = "a\rb").is_err());
+}
+
+#[cfg(feature = "minecraft")]
+#[test]
+fn server_properties() {
+	let file =
+		"#Minecraft server properties\nenable-rcon=true\nserver-ip=\nrcon.port = 25580\nrcon.password=p\\:ss\\u0021\n";
+	let properties = ServerProperties::from_reader(file.as_bytes()).unwrap();
+	assert_eq!(
+		properties,
+		ServerProperties {
+			enabled: true,
+			ip: None,
+			port: 25580,
+			password: "p:ss!".to_string(),
+		}
+	);
+	assert_eq!(properties.address(), "127.0.0.1:25580");
+	assert!(ServerProperties::from_reader("rcon.port=abc".as_bytes()).is_err());
+
+	// Lines ending in an unescaped backslash continue on the next line
+	let file = "server-ip=::\\\n  1\nrcon.pass\\\n\tword = se\\\n cret\\\\\n# comment \\\nrcon.port=25581";
+	let properties = ServerProperties::from_reader(file.as_bytes()).unwrap();
+	assert_eq!(properties.ip.as_deref(), Some("::1"));
+	assert_eq!(properties.password, "secret\\");
+	assert_eq!(properties.port, 25581);
+	assert_eq!(properties.address(), "[::1]:25581");
+}
+
+#[cfg(feature = "ark")]
+#[test]
+fn ark_list_players() {
+	let players = crate::ark::parse_list_players(
+		"\n0. Some, Name, 76561198000000000\n1. Ünïcode, 0002b3a5e5f64ed8b8a4c3d2e1f00000 \nNo Players Connected\n",
+	);
+	assert_eq!(players.len(), 2);
+	assert_eq!(players[0].name, "Some, Name");
+	assert_eq!(players[0].id, "76561198000000000");
+	assert_eq!(players[1].index, 1);
+	assert_eq!(players[1].id, "0002b3a5e5f64ed8b8a4c3d2e1f00000");
+	assert!(matches!(players[0].player_ref(), PlayerRef::Steam(_)));
+	assert!(matches!(players[1].player_ref(), PlayerRef::Eos(_)));
+}
+
+#[test]
+fn player_ids() {
+	let id = SteamId::from_account_id(22202);
+	assert_eq!(id.to_string(), "76561197960287930");
+	assert_eq!(
+		(id.steam2(), id.steam3()),
+		("STEAM_1:0:11101".to_string(), "[U:1:22202]".to_string())
+	);
+	for notation in ["76561197960287930", "STEAM_0:0:11101", "STEAM_1:0:11101", "[U:1:22202]"] {
+		assert_eq!(notation.parse::<SteamId>().unwrap(), id);
+	}
+	assert_eq!("STEAM_1:1:0".parse::<SteamId>().unwrap().account_id(), 1);
+	for invalid in [
+		"",
+		"+76561197960287930",
+		"76561197960287930a",
+		"STEAM_1:2:1",
+		"[G:1:1]",
+		"BOT",
+		"1234",
+	] {
+		assert!(matches!(invalid.parse::<SteamId>(), Err(Error::InvalidId(_))));
+	}
+	assert!(SteamId::from_u64(1234).is_err());
+
+	let eos: EosId = "0002B3A5E5F64ED8B8A4C3D2E1F00000".parse().unwrap();
+	assert_eq!(eos.as_str(), "0002b3a5e5f64ed8b8a4c3d2e1f00000");
+	assert!("0002b3a5".parse::<EosId>().is_err());
+
+	assert_eq!("[U:1:22202]".parse(), Ok(PlayerRef::Steam(id)));
+	assert_eq!("0002b3a5e5f64ed8b8a4c3d2e1f00000".parse(), Ok(PlayerRef::Eos(eos)));
+	assert_eq!("Steve".parse(), Ok(PlayerRef::Name("Steve".to_string())));
+	assert_eq!(
+		crate::command!("kick {player}", player = PlayerRef::Steam(id)).unwrap(),
+		"kick 76561197960287930"
+	);
+}
+
+#[cfg(all(feature = "client", feature = "minecraft"))]
+#[test]
+fn player_tracker() {
+	let mut tracker = PlayerTracker::minecraft();
+	let joined = tracker.update("There are 2 of a max of 20 players online: Steve, Alex");
+	assert_eq!(joined.len(), 2);
+	assert!(joined.iter().all(|e| e.kind == PlayerEventKind::Joined));
+
+	let events = tracker.update("There are 2 of a max of 20 players online: Alex, Herobrine");
+	let mut events: Vec<_> = events.into_iter().map(|e| (e.kind, e.player)).collect();
+	events.sort_by(|a, b| a.1.cmp(&b.1));
+	assert_eq!(
+		events,
+		vec![
+			(PlayerEventKind::Joined, "Herobrine".to_string()),
+			(PlayerEventKind::Left, "Steve".to_string())
+		]
+	);
+	assert!(tracker.update("There are 0 of a max of 20 players online:").len() == 2);
+}
+
+#[cfg(feature = "source")]
+#[test]
+fn source_log_lines() {
+	use crate::source::{parse_log_line, LogEvent};
+
+	let chat =
+		parse_log_line("L 10/17/2026 - 12:34:56: \"A <b>\"<2><STEAM_1:0:1234><CT>\" say_team \"rush b\"").unwrap();
+	assert_eq!(chat.timestamp, "10/17/2026 - 12:34:56");
+	match chat.event {
+		LogEvent::Chat {
+			player,
+			team_only,
+			message,
+		} => {
+			assert_eq!(
+				(player.name.as_str(), player.user_id, player.team.as_str()),
+				("A <b>\"", 2, "CT")
+			);
+			assert!(team_only);
+			assert_eq!(message, "rush b");
+		}
+		e => panic!("unexpected event {:?}", e),
+	}
+
+	let kill = parse_log_line(
+		"L 10/17/2026 - 12:35:00: \"K<3><[U:1:1]><CT>\" [1 2 3] killed \"V<4><BOT><TERRORIST>\" [4 5 6] with \"ak47\" (headshot)",
+	)
+	.unwrap();
+	assert!(
+		matches!(kill.event, LogEvent::Kill { ref weapon, ref victim, .. } if weapon == "ak47" && victim.steam_id == "BOT")
+	);
+	match kill.event {
+		LogEvent::Kill { killer, victim, .. } => {
+			assert_eq!(killer.steam_account(), Some(SteamId::from_account_id(1)));
+			assert_eq!(victim.steam_account(), None);
+		}
+		e => panic!("unexpected event {:?}", e),
+	}
+
+	let other = parse_log_line("L 10/17/2026 - 12:35:01: World triggered \"Round_Start\"").unwrap();
+	assert_eq!(
+		other.event,
+		LogEvent::Other("World triggered \"Round_Start\"".to_string())
+	);
+	assert!(parse_log_line("garbage").is_none());
+}
+
 /*
 #[tokio::test]
 async fn integration_test() {
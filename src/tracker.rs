@@ -0,0 +1,128 @@
+use std::{collections::HashSet, sync::Arc, time::SystemTime};
+
+use crate::{connection::SingleConnection, error::RconError};
+
+type PlayerParser = Arc<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+/// Whether a player joined or left, see [`PlayerEvent`](struct.PlayerEvent.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerEventKind {
+	/// The player appeared in the player list.
+	Joined,
+	/// The player disappeared from the player list.
+	Left,
+}
+
+/// A change in the player list detected by [`PlayerTracker`](struct.PlayerTracker.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerEvent {
+	/// Whether the player joined or left.
+	pub kind: PlayerEventKind,
+	/// The player, as identified by the parser of the tracker.
+	pub player: String,
+	/// When the change was detected.
+	pub at: SystemTime,
+}
+
+/// Tracks the players on a server by periodically running the game's player list command and comparing its output
+/// to the previous run, the backbone of welcome message or playtime tracking bots.
+///
+/// The first update reports every online player as joined.
+#[derive(Clone)]
+pub struct PlayerTracker {
+	command: String,
+	parser: PlayerParser,
+	players: HashSet<String>,
+}
+
+impl PlayerTracker {
+	/// Creates a tracker running `command`, using `parser` to extract a unique identifier of every player from its
+	/// response.
+	pub fn new(command: impl ToString, parser: impl Fn(&str) -> Vec<String> + Send + Sync + 'static) -> Self {
+		PlayerTracker {
+			command: command.to_string(),
+			parser: Arc::new(parser),
+			players: HashSet::new(),
+		}
+	}
+
+	/// A tracker for Ark servers, identifying players by their platform ID.
+	#[cfg(feature = "ark")]
+	pub fn ark() -> Self {
+		Self::new("ListPlayers", |response| {
+			crate::ark::parse_list_players(response)
+				.into_iter()
+				.map(|player| player.id)
+				.collect()
+		})
+	}
+
+	/// A tracker for Minecraft servers, identifying players by their name.
+	///
+	/// # Example
+	/// ```rust,no_run
+	/// use std::time::Duration;
+	/// use rercon::{Connection, PlayerTracker, Settings};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let connection = Connection::open("123.456.789.123:25575", "my_secret_password", Settings::minecraft()).await.unwrap();
+	///     let mut tracker = PlayerTracker::minecraft();
+	///     let mut interval = tokio::time::interval(Duration::from_secs(30));
+	///     loop {
+	///         interval.tick().await;
+	///         for event in tracker.poll(&connection).await.unwrap() {
+	///             println!("{:?}: {}", event.kind, event.player);
+	///         }
+	///     }
+	/// }
+	/// ```
+	#[cfg(feature = "minecraft")]
+	pub fn minecraft() -> Self {
+		Self::new("list", |response| match response.split_once(':') {
+			Some((_, players)) => crate::parse::comma_list(players).map(str::to_string).collect(),
+			None => Vec::new(),
+		})
+	}
+
+	/// The command that is used to list the players.
+	pub fn command(&self) -> &str {
+		&self.command
+	}
+
+	/// The players that were online during the last update.
+	pub fn players(&self) -> impl Iterator<Item = &str> {
+		self.players.iter().map(String::as_str)
+	}
+
+	/// Compares the response of the player list [`command`](#method.command) to the previous update, returning the
+	/// players that joined and left in the meantime.
+	pub fn update(&mut self, response: &str) -> Vec<PlayerEvent> {
+		let at = SystemTime::now();
+		let players: HashSet<String> = (self.parser)(response).into_iter().collect();
+		let event = |kind, player: &String| PlayerEvent {
+			kind,
+			player: player.clone(),
+			at,
+		};
+
+		let mut events: Vec<PlayerEvent> = self
+			.players
+			.difference(&players)
+			.map(|player| event(PlayerEventKind::Left, player))
+			.collect();
+		events.extend(
+			players
+				.difference(&self.players)
+				.map(|player| event(PlayerEventKind::Joined, player)),
+		);
+		self.players = players;
+		events
+	}
+
+	/// Runs the player list command on the connection and [`update`](#method.update)s the tracker with its response.
+	pub async fn poll(&mut self, connection: &SingleConnection) -> Result<Vec<PlayerEvent>, RconError> {
+		let response = connection.exec(&self.command).await?;
+		Ok(self.update(&response))
+	}
+}
@@ -0,0 +1,163 @@
+use std::{
+	collections::VecDeque,
+	fs::{File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::RconError;
+
+/// Records every command sent through a connection along with its full response, for audit trails and debugging.
+///
+/// Transcripts are enabled through [`Settings::transcript`](struct.Settings.html#structfield.transcript). They are
+/// cheap to clone, with all clones sharing the same entries, so the caller can keep a clone to retrieve them.
+///
+/// A transcript created through [`new`](#method.new) keeps every entry in memory for as long as it exists, so
+/// long-lived connections should use [`with_capacity`](#method.with_capacity) to only keep the most recent ones, or
+/// [`with_file`](#method.with_file) to keep none in memory at all.
+#[derive(Clone, Default)]
+pub struct Transcript {
+	inner: Arc<Mutex<TranscriptInner>>,
+}
+
+#[derive(Default)]
+struct TranscriptInner {
+	entries: VecDeque<TranscriptEntry>,
+	capacity: Option<usize>,
+	path: Option<PathBuf>,
+	file: Option<File>,
+}
+
+/// A single command and its outcome, recorded by a [`Transcript`](struct.Transcript.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+	/// When the command completed.
+	pub timestamp: SystemTime,
+	/// The command that was sent.
+	pub command: String,
+	/// The response, or a description of the error that occurred.
+	pub response: Result<String, String>,
+}
+
+impl Transcript {
+	/// Creates an empty transcript, kept in memory without a limit.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates an empty transcript kept in memory, which only keeps the most recent `capacity` entries, discarding
+	/// the oldest entry when a new one is recorded.
+	pub fn with_capacity(capacity: usize) -> Self {
+		let transcript = Self::new();
+		transcript.lock().capacity = Some(capacity);
+		transcript
+	}
+
+	/// Creates a transcript which appends every entry as a JSON line to the file at `path` as soon as it is recorded,
+	/// in the format of [`write_jsonl`](#method.write_jsonl). The entries are not kept in memory, so
+	/// [`entries`](#method.entries) is always empty. Entries that could not be written are lost.
+	pub fn with_file(path: impl Into<PathBuf>) -> Self {
+		let transcript = Self::new();
+		transcript.lock().path = Some(path.into());
+		transcript
+	}
+
+	/// Returns the entries recorded so far, oldest first.
+	pub fn entries(&self) -> Vec<TranscriptEntry> {
+		self.lock().entries.iter().cloned().collect()
+	}
+
+	/// Writes the entries recorded so far as JSON lines, one object per entry with the fields `timestamp` (in
+	/// milliseconds since the Unix epoch), `command`, and either `response` or `error`.
+	pub fn write_jsonl(&self, mut writer: impl Write) -> io::Result<()> {
+		for entry in &self.lock().entries {
+			writeln!(writer, "{}", entry.to_json())?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn record(&self, command: &[u8], result: &Result<String, RconError>) {
+		let entry = TranscriptEntry {
+			timestamp: SystemTime::now(),
+			command: String::from_utf8_lossy(command).into_owned(),
+			response: match result {
+				Ok(response) => Ok(response.clone()),
+				Err(e) => Err(e.to_string()),
+			},
+		};
+
+		let mut inner = self.lock();
+		if inner.path.is_some() {
+			// A transcript that can't be written must not fail the exec it records.
+			let _ = inner.append(&entry);
+			return;
+		}
+		if inner.capacity == Some(0) {
+			return;
+		}
+		if Some(inner.entries.len()) == inner.capacity {
+			inner.entries.pop_front();
+		}
+		inner.entries.push_back(entry);
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, TranscriptInner> {
+		// A panic while holding the lock can't leave the entries in an inconsistent state.
+		self.inner.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+impl TranscriptInner {
+	/// Appends an entry to the file, opening it if that did not happen yet.
+	fn append(&mut self, entry: &TranscriptEntry) -> io::Result<()> {
+		let file = match (&mut self.file, &self.path) {
+			(Some(file), _) => file,
+			(None, Some(path)) => self
+				.file
+				.insert(OpenOptions::new().create(true).append(true).open(path)?),
+			(None, None) => return Ok(()),
+		};
+		writeln!(file, "{}", entry.to_json())
+	}
+}
+
+impl TranscriptEntry {
+	fn to_json(&self) -> String {
+		let timestamp = self
+			.timestamp
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_millis();
+		let (key, value) = match &self.response {
+			Ok(response) => ("response", response),
+			Err(error) => ("error", error),
+		};
+		format!(
+			"{{\"timestamp\":{},\"command\":{},\"{}\":{}}}",
+			timestamp,
+			json_string(&self.command),
+			key,
+			json_string(value)
+		)
+	}
+}
+
+fn json_string(s: &str) -> String {
+	let mut result = String::with_capacity(s.len() + 2);
+	result.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => result.push_str("\\\""),
+			'\\' => result.push_str("\\\\"),
+			'\n' => result.push_str("\\n"),
+			'\r' => result.push_str("\\r"),
+			'\t' => result.push_str("\\t"),
+			c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+			c => result.push(c),
+		}
+	}
+	result.push('"');
+	result
+}